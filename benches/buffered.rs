@@ -0,0 +1,63 @@
+//! Compares writing records to a file through `stdout` one at a time and in blocks.
+//!
+//! Run with `cargo bench --bench buffered`. Each mode runs in a child process with its
+//! `stdout` redirected to a file.
+
+#![allow(clippy::unwrap_used)]
+#![allow(clippy::print_stdout)]
+
+use std::env;
+use std::fs::File;
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+const RECORDS: usize = 200_000;
+const MODE_ENV: &str = "ENV_LOGGER_BENCH_MODE";
+
+fn main() {
+    match env::var(MODE_ENV) {
+        Ok(mode) => log_records(&mode),
+        Err(_) => compare(),
+    }
+}
+
+fn compare() {
+    let path = env::temp_dir().join(format!("env_logger-bench-{}.log", std::process::id()));
+    for mode in ["per-record", "buffered"] {
+        let elapsed = run(mode, &path);
+        println!(
+            "{mode:>10}: {RECORDS} records in {elapsed:?} ({:?}/record)",
+            elapsed / RECORDS as u32
+        );
+    }
+    let _ = std::fs::remove_file(&path);
+}
+
+/// Runs this benchmark in a child process logging in `mode` to the file at `path`.
+fn run(mode: &str, path: &std::path::Path) -> Duration {
+    let start = Instant::now();
+    let status = Command::new(env::current_exe().unwrap())
+        .env(MODE_ENV, mode)
+        .env_remove("RUST_LOG")
+        .stdout(File::create(path).unwrap())
+        .status()
+        .unwrap();
+    assert!(status.success());
+    start.elapsed()
+}
+
+fn log_records(mode: &str) {
+    let mut builder = env_logger::Builder::new();
+    builder
+        .filter_level(log::LevelFilter::Info)
+        .target(env_logger::Target::Stdout);
+    if mode == "buffered" {
+        builder.buffered(64 * 1024);
+    }
+    builder.init();
+
+    for i in 0..RECORDS {
+        log::info!("processed item {i} of the batch");
+    }
+    log::logger().flush();
+}
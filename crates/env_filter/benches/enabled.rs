@@ -0,0 +1,106 @@
+#![allow(missing_docs)]
+
+use log::{Level, LevelFilter, Metadata};
+
+const SIZES: &[usize] = &[1, 16, 64, 256];
+
+/// A target matched by the most specific directive
+const MATCHED: &str = "crate7::module3::submodule";
+
+/// A target only matched by the global directive
+const UNMATCHED: &str = "unrelated::module::submodule";
+
+fn spec(size: usize) -> String {
+    let mut spec = String::from("warn");
+    for i in 0..size {
+        spec.push_str(&format!(",crate{}::module{}=info", i % 16, i / 16));
+    }
+    spec
+}
+
+fn filter(size: usize) -> env_filter::Filter {
+    env_filter::Builder::new()
+        .try_parse(&spec(size))
+        .unwrap()
+        .build()
+}
+
+fn metadata(target: &str) -> Metadata<'_> {
+    Metadata::builder()
+        .level(Level::Debug)
+        .target(target)
+        .build()
+}
+
+/// The lookup `Filter::enabled` used before directives were compiled into a trie
+mod linear {
+    use super::{spec, Level, LevelFilter, Metadata};
+
+    pub(crate) struct Filter(Vec<(Option<String>, LevelFilter)>);
+
+    impl Filter {
+        pub(crate) fn new(size: usize) -> Self {
+            let mut directives = spec(size)
+                .split(',')
+                .map(|d| match d.split_once('=') {
+                    Some((name, level)) => (Some(name.to_owned()), level.parse().unwrap()),
+                    None => (None, d.parse().unwrap()),
+                })
+                .collect::<Vec<_>>();
+            directives.sort_by_key(|(name, _)| name.as_ref().map(|n| n.len()).unwrap_or(0));
+            Self(directives)
+        }
+
+        pub(crate) fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+            let level: Level = metadata.level();
+            let target = metadata.target();
+            for (name, directive_level) in self.0.iter().rev() {
+                match name {
+                    Some(name) if !target.starts_with(&**name) => {}
+                    Some(..) | None => return level <= *directive_level,
+                }
+            }
+            false
+        }
+    }
+}
+
+mod matched {
+    use super::{filter, linear, metadata, MATCHED, SIZES};
+
+    #[divan::bench(args = SIZES)]
+    fn linear(bencher: divan::Bencher<'_, '_>, size: usize) {
+        let filter = linear::Filter::new(size);
+        let metadata = metadata(MATCHED);
+        bencher.bench_local(|| filter.enabled(divan::black_box(&metadata)));
+    }
+
+    #[divan::bench(args = SIZES)]
+    fn trie(bencher: divan::Bencher<'_, '_>, size: usize) {
+        let filter = filter(size);
+        let metadata = metadata(MATCHED);
+        bencher.bench_local(|| filter.enabled(divan::black_box(&metadata)));
+    }
+}
+
+mod unmatched {
+    use super::{filter, linear, metadata, SIZES, UNMATCHED};
+
+    #[divan::bench(args = SIZES)]
+    fn linear(bencher: divan::Bencher<'_, '_>, size: usize) {
+        let filter = linear::Filter::new(size);
+        let metadata = metadata(UNMATCHED);
+        bencher.bench_local(|| filter.enabled(divan::black_box(&metadata)));
+    }
+
+    #[divan::bench(args = SIZES)]
+    fn trie(bencher: divan::Bencher<'_, '_>, size: usize) {
+        let filter = filter(size);
+        let metadata = metadata(UNMATCHED);
+        bencher.bench_local(|| filter.enabled(divan::black_box(&metadata)));
+    }
+}
+
+fn main() {
+    divan::main();
+}
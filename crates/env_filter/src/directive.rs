@@ -1,4 +1,5 @@
-use alloc::string::String;
+use alloc::{boxed::Box, string::String, vec::Vec};
+use core::fmt;
 
 use log::Level;
 use log::LevelFilter;
@@ -9,14 +10,252 @@ pub(crate) struct Directive {
     pub(crate) level: LevelFilter,
 }
 
+/// A set of directives compiled for lookup by target.
+///
+/// Directive names are stored in a prefix trie so finding the most specific
+/// directive for a target costs one walk over the target, regardless of how
+/// many directives there are.
+#[derive(Clone)]
+pub(crate) struct Directives {
+    list: Vec<Directive>,
+    trie: Trie,
+}
+
+impl Directives {
+    pub(crate) fn new(list: Vec<Directive>) -> Self {
+        let mut trie = Trie::default();
+        for directive in &list {
+            trie.insert(
+                directive.name.as_deref().unwrap_or_default(),
+                directive.level,
+            );
+        }
+        Self { list, trie }
+    }
+
+    pub(crate) fn iter(&self) -> core::slice::Iter<'_, Directive> {
+        self.list.iter()
+    }
+
+    /// The level of the most specific directive matching `target`, if any.
+    pub(crate) fn level(&self, target: &str) -> Option<LevelFilter> {
+        self.trie.longest_prefix(target)
+    }
+}
+
+impl fmt::Debug for Directives {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.list.fmt(f)
+    }
+}
+
 // Check whether a level and target are enabled by the set of directives.
-pub(crate) fn enabled(directives: &[Directive], level: Level, target: &str) -> bool {
-    // Search for the longest match, the vector is assumed to be pre-sorted.
-    for directive in directives.iter().rev() {
-        match directive.name {
-            Some(ref name) if !target.starts_with(&**name) => {}
-            Some(..) | None => return level <= directive.level,
+pub(crate) fn enabled(directives: &Directives, level: Level, target: &str) -> bool {
+    // Search for the longest match
+    match directives.level(target) {
+        Some(directive_level) => level <= directive_level,
+        None => false,
+    }
+}
+
+/// A byte-wise radix trie of directive names.
+///
+/// Matching is on raw prefixes rather than `::` segments so that `example`
+/// still matches `examples::and_more_examples`, like a plain `starts_with`.
+#[derive(Debug, Clone)]
+struct Trie {
+    // The root is always `nodes[0]` and is keyed by the empty name
+    nodes: Vec<Node>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Node {
+    level: Option<LevelFilter>,
+    edges: Vec<Edge>,
+}
+
+#[derive(Debug, Clone)]
+struct Edge {
+    label: Box<[u8]>,
+    node: usize,
+}
+
+impl Default for Trie {
+    fn default() -> Self {
+        Self {
+            nodes: alloc::vec![Node::default()],
+        }
+    }
+}
+
+impl Trie {
+    /// Set the level for `name`, replacing any level already set for it.
+    fn insert(&mut self, name: &str, level: LevelFilter) {
+        let mut node = 0;
+        let mut rest = name.as_bytes();
+
+        while !rest.is_empty() {
+            let Some(i) = self.nodes[node]
+                .edges
+                .iter()
+                .position(|edge| edge.label[0] == rest[0])
+            else {
+                let child = self.push(Node::default());
+                self.nodes[node].edges.push(Edge {
+                    label: rest.into(),
+                    node: child,
+                });
+                node = child;
+                break;
+            };
+
+            let edge = &self.nodes[node].edges[i];
+            let common = common_prefix_len(&edge.label, rest);
+            if common < edge.label.len() {
+                // Split the edge so `name` ends (or diverges) on a node boundary
+                let (head, tail) = edge.label.split_at(common);
+                let (head, tail): (Box<[u8]>, Box<[u8]>) = (head.into(), tail.into());
+                let split = self.push(Node {
+                    level: None,
+                    edges: alloc::vec![Edge {
+                        label: tail,
+                        node: self.nodes[node].edges[i].node,
+                    }],
+                });
+                self.nodes[node].edges[i] = Edge {
+                    label: head,
+                    node: split,
+                };
+            }
+
+            node = self.nodes[node].edges[i].node;
+            rest = &rest[common..];
+        }
+
+        self.nodes[node].level = Some(level);
+    }
+
+    /// Find the level of the longest inserted name that `target` starts with.
+    fn longest_prefix(&self, target: &str) -> Option<LevelFilter> {
+        let mut node = &self.nodes[0];
+        let mut rest = target.as_bytes();
+        let mut level = node.level;
+
+        while let Some(&first) = rest.first() {
+            let Some(edge) = node.edges.iter().find(|edge| edge.label[0] == first) else {
+                break;
+            };
+            let Some(remaining) = rest.strip_prefix(&*edge.label) else {
+                break;
+            };
+
+            node = &self.nodes[edge.node];
+            rest = remaining;
+            if node.level.is_some() {
+                level = node.level;
+            }
+        }
+
+        level
+    }
+
+    fn push(&mut self, node: Node) -> usize {
+        self.nodes.push(node);
+        self.nodes.len() - 1
+    }
+}
+
+fn common_prefix_len(a: &[u8], b: &[u8]) -> usize {
+    a.iter().zip(b).take_while(|(a, b)| a == b).count()
+}
+
+#[cfg(test)]
+mod tests {
+    use log::{Level, LevelFilter};
+
+    use super::{enabled, Directive, Directives};
+
+    fn directives(dirs: &[(Option<&str>, LevelFilter)]) -> Directives {
+        Directives::new(
+            dirs.iter()
+                .map(|(name, level)| Directive {
+                    name: name.map(|s| s.to_owned()),
+                    level: *level,
+                })
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn match_raw_prefix() {
+        let dirs = directives(&[(Some("example"), LevelFilter::Debug)]);
+        assert!(enabled(&dirs, Level::Debug, "example"));
+        assert!(enabled(&dirs, Level::Debug, "example::test"));
+        assert!(enabled(&dirs, Level::Debug, "examples::and_more_examples"));
+        assert!(!enabled(&dirs, Level::Debug, "exampl"));
+        assert!(!enabled(&dirs, Level::Debug, "other"));
+    }
+
+    #[test]
+    fn match_split_edges() {
+        let dirs = directives(&[
+            (Some("crate1::module2"), LevelFilter::Trace),
+            (Some("crate1::mod"), LevelFilter::Warn),
+            (Some("crate1::module1"), LevelFilter::Debug),
+            (Some("crate1"), LevelFilter::Info),
+            (None, LevelFilter::Error),
+        ]);
+        assert_eq!(dirs.level("crate1::module2::x"), Some(LevelFilter::Trace));
+        assert_eq!(dirs.level("crate1::module1"), Some(LevelFilter::Debug));
+        assert_eq!(dirs.level("crate1::module3"), Some(LevelFilter::Warn));
+        assert_eq!(dirs.level("crate1::mo"), Some(LevelFilter::Info));
+        assert_eq!(dirs.level("crate2"), Some(LevelFilter::Error));
+        assert_eq!(dirs.level(""), Some(LevelFilter::Error));
+    }
+
+    #[test]
+    fn match_last_duplicate() {
+        let dirs = directives(&[
+            (Some("crate1"), LevelFilter::Info),
+            (Some("crate1"), LevelFilter::Off),
+        ]);
+        assert_eq!(dirs.level("crate1"), Some(LevelFilter::Off));
+    }
+
+    #[test]
+    fn match_agrees_with_linear_scan() {
+        let names = [
+            "a", "ab", "abc", "a::b", "a::bc", "b", "b::a", "bb", "a::", "a:",
+        ];
+        let targets = [
+            "", "a", "ab", "abc", "abcd", "a::b", "a::bc::d", "a::c", "a:", "b", "b::a", "bba", "c",
+        ];
+        let levels = [
+            LevelFilter::Off,
+            LevelFilter::Error,
+            LevelFilter::Warn,
+            LevelFilter::Info,
+            LevelFilter::Debug,
+        ];
+
+        let mut list = names
+            .iter()
+            .zip(levels.iter().cycle())
+            .map(|(name, level)| Directive {
+                name: Some((*name).to_owned()),
+                level: *level,
+            })
+            .collect::<Vec<_>>();
+        list.sort_by_key(|d| d.name.as_ref().map(|n| n.len()).unwrap_or(0));
+        let dirs = Directives::new(list.clone());
+
+        for target in targets {
+            let expected = list
+                .iter()
+                .rev()
+                .find(|d| target.starts_with(d.name.as_deref().unwrap_or_default()))
+                .map(|d| d.level);
+            assert_eq!(dirs.level(target), expected, "target: {target:?}");
         }
     }
-    false
 }
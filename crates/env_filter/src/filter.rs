@@ -1,4 +1,4 @@
-use alloc::{borrow::ToOwned, string::ToString, vec::Vec};
+use alloc::{borrow::ToOwned, vec::Vec};
 use core::{fmt, mem};
 
 use log::{LevelFilter, Metadata, Record};
@@ -7,6 +7,7 @@ use crate::enabled;
 use crate::parse_spec;
 use crate::parser::ParseResult;
 use crate::Directive;
+use crate::Directives;
 use crate::FilterOp;
 use crate::ParseError;
 
@@ -138,6 +139,13 @@ impl Builder {
         Ok(self)
     }
 
+    /// The directives added so far, as `(module, level)` pairs.
+    ///
+    /// A directive without a module applies to all log messages.
+    pub fn directives(&self) -> impl Iterator<Item = (Option<&str>, LevelFilter)> + '_ {
+        self.directives.iter().map(|d| (d.name.as_deref(), d.level))
+    }
+
     /// Build a log filter.
     pub fn build(&mut self) -> Filter {
         assert!(!self.built, "attempt to re-use consumed builder");
@@ -153,8 +161,8 @@ impl Builder {
         } else {
             // Consume directives.
             directives = mem::take(&mut self.directives);
-            // Sort the directives by length of their name, this allows a
-            // little more efficient lookup at runtime.
+            // Sort the directives by length of their name, from least to most
+            // specific.
             directives.sort_by(|a, b| {
                 let alen = a.name.as_ref().map(|a| a.len()).unwrap_or(0);
                 let blen = b.name.as_ref().map(|b| b.len()).unwrap_or(0);
@@ -163,7 +171,7 @@ impl Builder {
         }
 
         Filter {
-            directives: mem::take(&mut directives),
+            directives: Directives::new(mem::take(&mut directives)),
             filter: mem::take(&mut self.filter),
         }
     }
@@ -197,7 +205,7 @@ impl fmt::Debug for Builder {
 /// [`Builder`]: struct.Builder.html
 #[derive(Clone)]
 pub struct Filter {
-    directives: Vec<Directive>,
+    directives: Directives,
     filter: Option<FilterOp>,
 }
 
@@ -233,7 +241,26 @@ impl Filter {
         }
 
         if let Some(filter) = self.filter.as_ref() {
-            if !filter.is_match(&record.args().to_string()) {
+            if !filter.is_match_args(record.args()) {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    /// Checks if this record matches the configured filter, given its already formatted message.
+    ///
+    /// This is the same as [`Filter::matches`] except that `message` is checked against the
+    /// message filter instead of formatting [`Record::args`] again.
+    /// `message` is expected to be the formatted `record.args()`.
+    pub fn matches_message(&self, record: &Record<'_>, message: &str) -> bool {
+        if !self.enabled(record.metadata()) {
+            return false;
+        }
+
+        if let Some(filter) = self.filter.as_ref() {
+            if !filter.is_match(message) {
                 return false;
             }
         }
@@ -241,6 +268,42 @@ impl Filter {
         true
     }
 
+    /// Whether records are also filtered by their message.
+    ///
+    /// When this is `true`, loggers that format the message anyway can format it up front
+    /// and pass it to [`Filter::matches_message`].
+    pub fn has_message_filter(&self) -> bool {
+        self.filter.is_some()
+    }
+
+    /// Returns a [`Builder`] with the directives and message filter of this filter,
+    /// to build a modified copy of it.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use log::LevelFilter;
+    /// use env_filter::Builder;
+    ///
+    /// let filter = Builder::new().try_parse("warn,hyper=debug").unwrap().build();
+    /// let verbose = filter.to_builder().filter_level(LevelFilter::Info).build();
+    /// assert_eq!(verbose.to_string(), "info,hyper=debug");
+    /// ```
+    pub fn to_builder(&self) -> Builder {
+        Builder {
+            directives: self.directives.iter().cloned().collect(),
+            filter: self.filter.clone(),
+            built: false,
+        }
+    }
+
+    /// The directives of the filter, as `(module, level)` pairs, from least to most specific.
+    ///
+    /// A directive without a module applies to all log messages.
+    pub fn directives(&self) -> impl Iterator<Item = (Option<&str>, LevelFilter)> + '_ {
+        self.directives.iter().map(|d| (d.name.as_deref(), d.level))
+    }
+
     /// Determines if a log message with the specified metadata would be logged.
     pub fn enabled(&self, metadata: &Metadata<'_>) -> bool {
         let level = metadata.level();
@@ -250,6 +313,32 @@ impl Filter {
     }
 }
 
+/// Formats the filter in the same form it is parsed from, like `warn,hyper=debug/foo`.
+impl fmt::Display for Filter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, directive) in self.directives.iter().enumerate() {
+            if 0 < i {
+                f.write_str(",")?;
+            }
+            if let Some(name) = &directive.name {
+                write!(f, "{name}=")?;
+            }
+            f.write_str(match directive.level {
+                LevelFilter::Off => "off",
+                LevelFilter::Error => "error",
+                LevelFilter::Warn => "warn",
+                LevelFilter::Info => "info",
+                LevelFilter::Debug => "debug",
+                LevelFilter::Trace => "trace",
+            })?;
+        }
+        if let Some(filter) = &self.filter {
+            write!(f, "/{filter}")?;
+        }
+        Ok(())
+    }
+}
+
 impl fmt::Debug for Filter {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("Filter")
@@ -264,11 +353,11 @@ mod tests {
     use log::{Level, LevelFilter};
     use snapbox::{assert_data_eq, str};
 
-    use super::{enabled, Builder, Directive, Filter};
+    use super::{enabled, Builder, Directive, Directives, Filter};
 
     fn make_logger_filter(dirs: Vec<Directive>) -> Filter {
         let mut logger = Builder::new().build();
-        logger.directives = dirs;
+        logger.directives = Directives::new(dirs);
         logger
     }
 
@@ -499,6 +588,81 @@ mod tests {
         );
     }
 
+    #[test]
+    fn matches_message_filter() {
+        let filter = Builder::new().try_parse("info/foo").unwrap().build();
+        assert!(filter.has_message_filter());
+
+        fn record(args: core::fmt::Arguments<'_>) -> log::Record<'_> {
+            log::Record::builder()
+                .level(Level::Info)
+                .target("crate1")
+                .args(args)
+                .build()
+        }
+
+        assert!(filter.matches(&record(format_args!("foo"))));
+        assert!(filter.matches(&record(format_args!("{}o", "fo"))));
+        assert!(!filter.matches(&record(format_args!("{}", "bar"))));
+        assert!(filter.matches_message(&record(format_args!("{}", "bar")), "foo"));
+        assert!(!filter.matches_message(&record(format_args!("{}", "foo")), "bar"));
+
+        let filter = Builder::new().try_parse("info").unwrap().build();
+        assert!(!filter.has_message_filter());
+        assert!(filter.matches_message(&record(format_args!("foo")), "bar"));
+    }
+
+    #[test]
+    fn filter_display_round_trips() {
+        for spec in [
+            "error",
+            "info,crate1=off,crate1::mod1=trace",
+            "warn/foo",
+            "crate1=debug",
+        ] {
+            let filter = Builder::new().try_parse(spec).unwrap().build();
+            assert_eq!(filter.to_string(), spec);
+        }
+        assert_eq!(Builder::new().build().to_string(), "error");
+        assert_eq!(
+            Builder::new()
+                .try_parse("crate1::mod1=warn,info")
+                .unwrap()
+                .build()
+                .directives()
+                .collect::<Vec<_>>(),
+            [
+                (None, LevelFilter::Info),
+                (Some("crate1::mod1"), LevelFilter::Warn)
+            ]
+        );
+        assert_eq!(
+            Builder::new()
+                .try_parse("crate2,INFO")
+                .unwrap()
+                .build()
+                .to_string(),
+            "info,crate2=trace"
+        );
+    }
+
+    #[test]
+    fn builder_directives() {
+        let mut builder = Builder::new();
+        builder
+            .try_parse("info,crate1::mod1=warn,crate2")
+            .unwrap()
+            .filter_module("crate1::mod1", LevelFilter::Debug);
+        assert_eq!(
+            builder.directives().collect::<Vec<_>>(),
+            [
+                (None, LevelFilter::Info),
+                (Some("crate1::mod1"), LevelFilter::Debug),
+                (Some("crate2"), LevelFilter::Trace),
+            ]
+        );
+    }
+
     #[test]
     fn match_full_path() {
         let logger = make_logger_filter(vec![
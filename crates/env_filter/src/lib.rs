@@ -57,6 +57,7 @@ mod parser;
 
 use directive::enabled;
 use directive::Directive;
+use directive::Directives;
 use op::FilterOp;
 use parser::parse_spec;
 
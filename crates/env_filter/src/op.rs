@@ -1,4 +1,6 @@
 use alloc::string::{String, ToString};
+#[cfg(not(feature = "regex"))]
+use alloc::vec::Vec;
 use core::fmt;
 
 #[derive(Debug, Clone)]
@@ -7,6 +9,9 @@ pub(crate) struct FilterOp {
     inner: regex::Regex,
     #[cfg(not(feature = "regex"))]
     inner: String,
+    // Longest proper prefix of `inner[..=i]` that is also its suffix, for streaming search
+    #[cfg(not(feature = "regex"))]
+    fallback: Vec<usize>,
 }
 
 #[cfg(feature = "regex")]
@@ -21,19 +26,85 @@ impl FilterOp {
     pub(crate) fn is_match(&self, s: &str) -> bool {
         self.inner.is_match(s)
     }
+
+    pub(crate) fn is_match_args(&self, args: &fmt::Arguments<'_>) -> bool {
+        match args.as_str() {
+            Some(s) => self.is_match(s),
+            // A regex needs the whole message up front
+            None => self.is_match(&args.to_string()),
+        }
+    }
 }
 
 #[cfg(not(feature = "regex"))]
 impl FilterOp {
     pub(crate) fn new(spec: &str) -> Result<Self, String> {
+        let needle = spec.as_bytes();
+        let mut fallback = alloc::vec![0; needle.len()];
+        let mut len = 0;
+        for i in 1..needle.len() {
+            while 0 < len && needle[i] != needle[len] {
+                len = fallback[len - 1];
+            }
+            if needle[i] == needle[len] {
+                len += 1;
+            }
+            fallback[i] = len;
+        }
+
         Ok(Self {
             inner: spec.to_string(),
+            fallback,
         })
     }
 
     pub(crate) fn is_match(&self, s: &str) -> bool {
         s.contains(&self.inner)
     }
+
+    pub(crate) fn is_match_args(&self, args: &fmt::Arguments<'_>) -> bool {
+        if let Some(s) = args.as_str() {
+            return self.is_match(s);
+        }
+
+        // Search the message as it is formatted, rather than collecting it first
+        let mut search = Search {
+            op: self,
+            matched: 0,
+            found: self.inner.is_empty(),
+        };
+        let _ = fmt::write(&mut search, *args);
+        search.found
+    }
+}
+
+/// A substring search fed one formatted piece at a time.
+#[cfg(not(feature = "regex"))]
+struct Search<'a> {
+    op: &'a FilterOp,
+    matched: usize,
+    found: bool,
+}
+
+#[cfg(not(feature = "regex"))]
+impl fmt::Write for Search<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let needle = self.op.inner.as_bytes();
+        for &b in s.as_bytes() {
+            if self.found {
+                // Stop formatting, the rest of the message can't change the result
+                return Err(fmt::Error);
+            }
+            while 0 < self.matched && b != needle[self.matched] {
+                self.matched = self.op.fallback[self.matched - 1];
+            }
+            if b == needle[self.matched] {
+                self.matched += 1;
+            }
+            self.found = self.matched == needle.len();
+        }
+        Ok(())
+    }
 }
 
 impl fmt::Display for FilterOp {
@@ -41,3 +112,55 @@ impl fmt::Display for FilterOp {
         self.inner.fmt(f)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use core::fmt;
+
+    use super::FilterOp;
+
+    /// Formats each part with a separate write
+    struct Parts<'a>(&'a [&'a str]);
+
+    impl fmt::Display for Parts<'_> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            for part in self.0 {
+                f.write_str(part)?;
+            }
+            Ok(())
+        }
+    }
+
+    fn is_match(spec: &str, parts: &[&str]) -> bool {
+        FilterOp::new(spec)
+            .unwrap()
+            .is_match_args(&format_args!("{}", Parts(parts)))
+    }
+
+    #[test]
+    fn is_match_args() {
+        assert!(is_match("aab", &["aab"]));
+        assert!(is_match("aab", &["xa", "abx"]));
+        assert!(is_match("aab", &["a", "a", "b"]));
+        assert!(is_match("aab", &["aaa", "b"]));
+        assert!(!is_match("aab", &["ab", "aba"]));
+        assert!(!is_match("aab", &["a"]));
+        assert!(FilterOp::new("aab")
+            .unwrap()
+            .is_match_args(&format_args!("aab")));
+    }
+
+    #[test]
+    fn is_match_args_empty() {
+        assert!(is_match("", &[]));
+        assert!(is_match("", &[""]));
+        assert!(is_match("", &["a"]));
+    }
+
+    #[test]
+    #[cfg(feature = "regex")]
+    fn is_match_args_regex() {
+        assert!(is_match("[0-9]s", &["5", "s"]));
+        assert!(!is_match("[0-9]s", &["5", " s"]));
+    }
+}
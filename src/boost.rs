@@ -0,0 +1,264 @@
+//! Raising the level for a single thread or task.
+//!
+//! A boost logs records up to its level on the current thread, regardless of the
+//! filter, while every other thread keeps logging as before. This is useful to trace
+//! one request end to end without turning up the logging of the whole process.
+//!
+//! Records logged only because of a boost bypass the directives and the message filter.
+//!
+//! # Examples
+//!
+//! ```
+//! use env_logger::boost::BoostGuard;
+//! use log::LevelFilter;
+//!
+//! env_logger::Builder::new().parse_filters("info").init();
+//!
+//! {
+//!     let _boost = BoostGuard::new(LevelFilter::Trace);
+//!     assert!(log::log_enabled!(log::Level::Trace));
+//! }
+//!
+//! assert!(!log::log_enabled!(log::Level::Trace));
+//! ```
+//!
+//! With async code, a task can move between threads while it runs, so wrap its future
+//! in [`Boosted`] instead.
+
+use std::cell::Cell;
+use std::future::Future;
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::task::{Context, Poll};
+
+use log::{Level, LevelFilter};
+
+thread_local! {
+    static LEVEL: Cell<LevelFilter> = const { Cell::new(LevelFilter::Off) };
+}
+
+/// The number of active boosts at each level, indexed by `LevelFilter as usize`.
+static ACTIVE: [AtomicUsize; 6] = [
+    AtomicUsize::new(0),
+    AtomicUsize::new(0),
+    AtomicUsize::new(0),
+    AtomicUsize::new(0),
+    AtomicUsize::new(0),
+    AtomicUsize::new(0),
+];
+
+/// The boosted level of the current thread, or [`LevelFilter::Off`] if it isn't boosted.
+pub fn level() -> LevelFilter {
+    LEVEL.try_with(Cell::get).unwrap_or(LevelFilter::Off)
+}
+
+/// Whether records at `level` are logged on the current thread because of a boost.
+pub(crate) fn enabled(level: Level) -> bool {
+    level <= self::level()
+}
+
+/// The highest level boosted on any thread.
+pub(crate) fn max_level() -> LevelFilter {
+    LevelFilter::iter()
+        .filter(|level| ACTIVE[*level as usize].load(Ordering::Acquire) != 0)
+        .last()
+        .unwrap_or(LevelFilter::Off)
+}
+
+/// Raises the level of the current thread until it is dropped.
+///
+/// Boosts can be nested, and the highest level applies. Guards must be dropped in
+/// the reverse order they were created in.
+///
+/// See the [module documentation][self] for more details.
+#[must_use = "the boost only lasts until the guard is dropped"]
+#[derive(Debug)]
+pub struct BoostGuard {
+    previous: LevelFilter,
+    _active: Active,
+    // The boost belongs to the thread it was created on
+    _not_send: PhantomData<*const ()>,
+}
+
+impl BoostGuard {
+    /// Logs records up to `level` on the current thread.
+    pub fn new(level: LevelFilter) -> Self {
+        let active = Active::new(level);
+        Self {
+            previous: enter(level),
+            _active: active,
+            _not_send: PhantomData,
+        }
+    }
+}
+
+impl Drop for BoostGuard {
+    fn drop(&mut self) {
+        exit(self.previous);
+    }
+}
+
+/// A future that raises the level of whichever thread polls it.
+///
+/// The boost lasts until the future is dropped, but only applies while the future is
+/// being polled, so it follows an async task across the threads of a runtime.
+///
+/// # Examples
+///
+/// ```
+/// use env_logger::boost::Boosted;
+/// use log::LevelFilter;
+///
+/// # async fn handle_request() {}
+/// # async fn serve() {
+/// Boosted::new(LevelFilter::Trace, handle_request()).await;
+/// # }
+/// ```
+#[must_use = "futures do nothing unless polled"]
+#[derive(Debug)]
+pub struct Boosted<F> {
+    level: LevelFilter,
+    future: Pin<Box<F>>,
+    _active: Active,
+}
+
+impl<F: Future> Boosted<F> {
+    /// Logs records up to `level` while `future` is being polled.
+    pub fn new(level: LevelFilter, future: F) -> Self {
+        Self {
+            level,
+            future: Box::pin(future),
+            _active: Active::new(level),
+        }
+    }
+}
+
+impl<F: Future> Future for Boosted<F> {
+    type Output = F::Output;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let previous = enter(self.level);
+        let poll = self.future.as_mut().poll(cx);
+        exit(previous);
+        poll
+    }
+}
+
+/// Raises the level of the current thread, returning its previous level.
+fn enter(level: LevelFilter) -> LevelFilter {
+    LEVEL
+        .try_with(|current| current.replace(current.get().max(level)))
+        .unwrap_or(LevelFilter::Off)
+}
+
+fn exit(previous: LevelFilter) {
+    let _ = LEVEL.try_with(|current| current.set(previous));
+}
+
+/// Counts a boost towards [`max_level`] for as long as it's alive.
+#[derive(Debug)]
+struct Active(LevelFilter);
+
+impl Active {
+    fn new(level: LevelFilter) -> Self {
+        if ACTIVE[level as usize].fetch_add(1, Ordering::AcqRel) == 0 {
+            crate::reload::refresh_max_level();
+        }
+        Self(level)
+    }
+}
+
+impl Drop for Active {
+    fn drop(&mut self) {
+        if ACTIVE[self.0 as usize].fetch_sub(1, Ordering::AcqRel) == 1 {
+            crate::reload::refresh_max_level();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::future::Future;
+    use std::pin::Pin;
+    use std::sync::Arc;
+    use std::task::{Context, Poll, Wake, Waker};
+
+    use log::{Level, LevelFilter, Log as _, Metadata};
+
+    use super::{level, BoostGuard, Boosted};
+    use crate::Builder;
+
+    fn enabled(logger: &crate::Logger, level: Level) -> bool {
+        logger.enabled(&Metadata::builder().level(level).target("app").build())
+    }
+
+    #[test]
+    fn boost_current_thread() {
+        let logger = Builder::new().parse_filters("info").build();
+        assert!(!enabled(&logger, Level::Trace));
+
+        {
+            let _trace = BoostGuard::new(LevelFilter::Trace);
+            {
+                // The highest level applies
+                let _debug = BoostGuard::new(LevelFilter::Debug);
+                assert!(enabled(&logger, Level::Trace));
+            }
+            assert!(enabled(&logger, Level::Trace));
+
+            std::thread::scope(|s| {
+                s.spawn(|| {
+                    assert!(!enabled(&logger, Level::Debug));
+                    assert!(enabled(&logger, Level::Info));
+                });
+            });
+        }
+
+        assert!(!enabled(&logger, Level::Debug));
+    }
+
+    struct NoopWaker;
+
+    impl Wake for NoopWaker {
+        fn wake(self: Arc<Self>) {}
+    }
+
+    /// Records the boosted level each time it's polled, and is ready on the second poll.
+    struct Levels(Vec<LevelFilter>);
+
+    impl Future for Levels {
+        type Output = Vec<LevelFilter>;
+
+        fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+            self.0.push(level());
+            if self.0.len() < 2 {
+                cx.waker().wake_by_ref();
+                Poll::Pending
+            } else {
+                Poll::Ready(std::mem::take(&mut self.0))
+            }
+        }
+    }
+
+    fn poll<F: Future + Unpin>(future: &mut F) -> Poll<F::Output> {
+        let waker = Waker::from(Arc::new(NoopWaker));
+        Pin::new(future).poll(&mut Context::from_waker(&waker))
+    }
+
+    #[test]
+    fn boost_follows_future() {
+        let mut future = Boosted::new(LevelFilter::Debug, Levels(Vec::new()));
+
+        assert!(poll(&mut future).is_pending());
+        assert_eq!(level(), LevelFilter::Off);
+
+        let levels = std::thread::spawn(move || match poll(&mut future) {
+            Poll::Ready(levels) => levels,
+            Poll::Pending => panic!("future should be ready"),
+        })
+        .join()
+        .unwrap();
+        assert_eq!(levels, [LevelFilter::Debug, LevelFilter::Debug]);
+    }
+}
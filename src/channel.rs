@@ -0,0 +1,270 @@
+//! Sending records to a channel.
+//!
+//! Instead of writing formatted text to the target, the logger can send each record as an
+//! [`OwnedRecord`] to an [`mpsc`] channel with [`Builder::channel`], like for an
+//! application that shows records in its own interface and filters or renders them
+//! itself:
+//!
+//! ```
+//! use std::sync::mpsc;
+//!
+//! use env_logger::Builder;
+//!
+//! let (sender, receiver) = mpsc::sync_channel(1024);
+//! Builder::new().channel(sender).init();
+//!
+//! log::warn!("disk almost full");
+//!
+//! for record in receiver.try_iter() {
+//!     println!("[{}] {}", record.level(), record.message());
+//! }
+//! ```
+//!
+//! Sending never blocks the logging thread: when a bounded channel is full, the record is
+//! dropped. Records are also dropped once the receiver is gone.
+//!
+//! [`Builder::channel`]: crate::Builder::channel
+//! [`mpsc`]: std::sync::mpsc
+
+use std::sync::mpsc::{Sender, SyncSender};
+use std::sync::{Mutex, PoisonError};
+use std::thread;
+use std::time::SystemTime;
+
+use log::{Level, Record};
+
+/// A record that owns its data, to be sent to another thread.
+#[derive(Clone, Debug)]
+pub struct OwnedRecord {
+    level: Level,
+    target: String,
+    module_path: Option<String>,
+    file: Option<String>,
+    line: Option<u32>,
+    message: String,
+    key_values: Vec<(String, String)>,
+    timestamp: SystemTime,
+    thread: Option<String>,
+}
+
+impl OwnedRecord {
+    /// Copies a record logged on the current thread.
+    pub fn new(record: &Record<'_>) -> Self {
+        Self {
+            level: record.level(),
+            target: record.target().to_owned(),
+            module_path: record.module_path().map(str::to_owned),
+            file: record.file().map(str::to_owned),
+            line: record.line(),
+            message: record.args().to_string(),
+            key_values: key_values(record),
+            timestamp: SystemTime::now(),
+            thread: thread::current().name().map(str::to_owned),
+        }
+    }
+
+    /// The level of the record.
+    pub fn level(&self) -> Level {
+        self.level
+    }
+
+    /// The target of the record.
+    pub fn target(&self) -> &str {
+        &self.target
+    }
+
+    /// The module of the record, if known.
+    pub fn module_path(&self) -> Option<&str> {
+        self.module_path.as_deref()
+    }
+
+    /// The source file of the record, if known.
+    pub fn file(&self) -> Option<&str> {
+        self.file.as_deref()
+    }
+
+    /// The source line of the record, if known.
+    pub fn line(&self) -> Option<u32> {
+        self.line
+    }
+
+    /// The formatted message of the record.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// The key-values of the record, with their values formatted.
+    ///
+    /// This is always empty without the `kv` feature.
+    pub fn key_values(&self) -> &[(String, String)] {
+        &self.key_values
+    }
+
+    /// When the record was logged.
+    pub fn timestamp(&self) -> SystemTime {
+        self.timestamp
+    }
+
+    /// The name of the thread that logged the record, if it has one.
+    pub fn thread(&self) -> Option<&str> {
+        self.thread.as_deref()
+    }
+}
+
+#[cfg(feature = "kv")]
+fn key_values(record: &Record<'_>) -> Vec<(String, String)> {
+    use log::kv::{Error, Key, Value, VisitSource};
+
+    struct Collect(Vec<(String, String)>);
+
+    impl<'kvs> VisitSource<'kvs> for Collect {
+        fn visit_pair(&mut self, key: Key<'kvs>, value: Value<'kvs>) -> Result<(), Error> {
+            self.0.push((key.to_string(), value.to_string()));
+            Ok(())
+        }
+    }
+
+    let mut collect = Collect(Vec::new());
+    let _ = record.key_values().visit(&mut collect);
+    collect.0
+}
+
+#[cfg(not(feature = "kv"))]
+fn key_values(_: &Record<'_>) -> Vec<(String, String)> {
+    Vec::new()
+}
+
+/// The sending half of a channel of records, either unbounded or bounded.
+///
+/// It's made from a [`Sender`] or [`SyncSender`] with [`From`].
+#[derive(Clone, Debug)]
+pub struct RecordSender {
+    inner: Inner,
+}
+
+#[derive(Debug)]
+enum Inner {
+    // `Sender` is only `Sync` since Rust 1.72
+    Unbounded(Mutex<Sender<OwnedRecord>>),
+    Bounded(SyncSender<OwnedRecord>),
+}
+
+impl Clone for Inner {
+    fn clone(&self) -> Self {
+        match self {
+            Self::Unbounded(sender) => Self::Unbounded(Mutex::new(
+                sender
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .clone(),
+            )),
+            Self::Bounded(sender) => Self::Bounded(sender.clone()),
+        }
+    }
+}
+
+impl RecordSender {
+    /// Sends a copy of `record`, dropping it if the channel is full or disconnected.
+    pub(crate) fn send(&self, record: &Record<'_>) {
+        let record = OwnedRecord::new(record);
+        match &self.inner {
+            Inner::Unbounded(sender) => {
+                let _ = sender
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .send(record);
+            }
+            Inner::Bounded(sender) => {
+                let _ = sender.try_send(record);
+            }
+        }
+    }
+}
+
+impl From<Sender<OwnedRecord>> for RecordSender {
+    fn from(sender: Sender<OwnedRecord>) -> Self {
+        Self {
+            inner: Inner::Unbounded(Mutex::new(sender)),
+        }
+    }
+}
+
+impl From<SyncSender<OwnedRecord>> for RecordSender {
+    fn from(sender: SyncSender<OwnedRecord>) -> Self {
+        Self {
+            inner: Inner::Bounded(sender),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc;
+
+    use log::{Level, Record};
+
+    use super::RecordSender;
+
+    #[test]
+    fn send_owned_record() {
+        let (sender, receiver) = mpsc::channel();
+        let sender = RecordSender::from(sender);
+
+        sender.send(
+            &Record::builder()
+                .level(Level::Warn)
+                .target("app::db")
+                .module_path(Some("app::db"))
+                .file(Some("src/db.rs"))
+                .line(Some(7))
+                .args(format_args!("slow query: {}ms", 250))
+                .build(),
+        );
+
+        let record = receiver.try_recv().unwrap();
+        assert_eq!(record.level(), Level::Warn);
+        assert_eq!(record.target(), "app::db");
+        assert_eq!(record.module_path(), Some("app::db"));
+        assert_eq!(record.file(), Some("src/db.rs"));
+        assert_eq!(record.line(), Some(7));
+        assert_eq!(record.message(), "slow query: 250ms");
+        assert_eq!(record.thread(), std::thread::current().name());
+    }
+
+    #[test]
+    fn drop_records_when_full() {
+        let (sender, receiver) = mpsc::sync_channel(1);
+        let sender = RecordSender::from(sender);
+
+        for message in ["first", "second"] {
+            sender.send(&Record::builder().args(format_args!("{message}")).build());
+        }
+
+        let messages: Vec<_> = receiver.try_iter().map(|r| r.message).collect();
+        assert_eq!(messages, ["first"]);
+    }
+
+    #[test]
+    #[cfg(feature = "kv")]
+    fn capture_key_values() {
+        let (sender, receiver) = mpsc::channel();
+        let sender = RecordSender::from(sender);
+
+        let kvs = [("user", "alice"), ("id", "42")];
+        sender.send(
+            &Record::builder()
+                .args(format_args!("login"))
+                .key_values(&kvs)
+                .build(),
+        );
+
+        let record = receiver.try_recv().unwrap();
+        assert_eq!(
+            record.key_values(),
+            [
+                ("user".to_owned(), "alice".to_owned()),
+                ("id".to_owned(), "42".to_owned())
+            ]
+        );
+    }
+}
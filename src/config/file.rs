@@ -0,0 +1,833 @@
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use log::{Level, LevelFilter};
+
+use crate::config::{Config, Source};
+use crate::fmt::{Target, TimestampPrecision, WriteStyle};
+use crate::logger::Builder;
+use crate::writer::{Framing, WriteErrorPolicy};
+
+/// Settings loaded from a configuration file.
+///
+/// # File format
+///
+/// The file uses a small INI-like format of its own. **It isn't TOML**: the `[filters]`
+/// section holds bare directives rather than `key = value` pairs, so TOML parsers and
+/// validators reject it. Give it a name like `logging.conf` rather than `logging.toml`.
+///
+/// The file has one setting per line. A `#` at the start of a line or after whitespace
+/// starts a comment that runs to the end of the line, so a `#` inside a value, a
+/// directive or a message filter is kept. Values may be quoted, and quoted values
+/// support the `\n`, `\r`, `\t`, `\0`, `\\` and `\"` escapes.
+///
+/// Top-level settings come first, followed by these optional sections, in order:
+///
+/// - `[format]`: the [default format][crate::fmt::ConfigurableFormat].
+/// - `[flight_recorder]`: the [flight recorder][Builder::flight_recorder]. It needs both
+///   `capacity` and `level`.
+/// - `[filters]`: one directive per line, in the same form as the `RUST_LOG` environment
+///   variable. A filter line may be quoted to keep a message filter exactly as written,
+///   such as a regex containing ` #`.
+///
+/// ```text
+/// target = "stdout"           # `stdout` or `stderr`
+/// write_style = "never"       # `auto`, `always` or `never`
+/// framing = "nul"             # `newline`, `nul`, `length_prefixed` or `octet_counting`
+/// buffered = 65536            # a capacity in bytes
+/// flush_interval_ms = 500
+/// flush_level = "warn"
+/// write_errors = "stderr"     # `ignore`, `stderr`, `stdout`, or failures before stopping
+/// log_panics = true
+/// log_panic_backtraces = false
+///
+/// [format]
+/// timestamp = "millis"        # `seconds`, `millis`, `micros`, `nanos` or `false`
+/// level = true
+/// module_path = false
+/// target = true
+/// file = true
+/// line_number = true
+/// indent = 4                  # a number of spaces or `false`
+/// suffix = "\n"
+/// sanitize = true
+/// max_message_len = 4096      # a number of bytes or `false`
+///
+/// [flight_recorder]
+/// capacity = 100
+/// level = "debug"
+/// trigger = "error"
+///
+/// [filters]
+/// info
+/// hyper=debug                 # the HTTP client
+/// /request                    # only log messages containing `request`
+/// ```
+///
+/// Each setting calls the [`Builder`] method of the same name, like [`Builder::buffered`],
+/// or [`Builder::format_sanitize`] for `sanitize` in `[format]`. `flush_interval_ms` is
+/// [`Builder::flush_interval`] in milliseconds, and `write_errors` is
+/// [`Builder::write_error_policy`]. Other targets, like syslog or a pipe, and write error
+/// callbacks can only be set in code.
+///
+/// Settings that are left out keep whatever value the [`Builder`] already has.
+///
+/// # Examples
+///
+/// ```no_run
+/// use env_logger::config::ConfigFile;
+/// use env_logger::Builder;
+///
+/// let file = ConfigFile::load("/etc/myapp/logging.conf")?;
+/// Builder::new().apply_config_file(&file).init();
+/// # Ok::<(), env_logger::config::ConfigFileError>(())
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct ConfigFile {
+    path: Option<PathBuf>,
+    target: Option<Stream>,
+    write_style: Option<WriteStyle>,
+    timestamp: Option<Option<TimestampPrecision>>,
+    level: Option<bool>,
+    module_path: Option<bool>,
+    format_target: Option<bool>,
+    file: Option<bool>,
+    line_number: Option<bool>,
+    indent: Option<Option<usize>>,
+    suffix: Option<String>,
+    sanitize: Option<bool>,
+    max_message_len: Option<Option<usize>>,
+    framing: Option<Framing>,
+    buffered: Option<usize>,
+    flush_interval: Option<Duration>,
+    flush_level: Option<Level>,
+    write_errors: Option<OnWriteError>,
+    log_panics: Option<bool>,
+    log_panic_backtraces: Option<bool>,
+    recorder_capacity: Option<usize>,
+    recorder_level: Option<LevelFilter>,
+    recorder_trigger: Option<Level>,
+    directives: Vec<String>,
+    message_filter: Option<String>,
+}
+
+#[derive(Copy, Clone, Debug)]
+enum Stream {
+    Stdout,
+    Stderr,
+}
+
+/// A [`WriteErrorPolicy`] that can be written in a file.
+#[derive(Copy, Clone, Debug)]
+enum OnWriteError {
+    Ignore,
+    Fallback(Stream),
+    DisableAfter(u64),
+}
+
+/// A section of the file, in the order they must appear.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum Section {
+    Settings,
+    Format,
+    FlightRecorder,
+    Filters,
+}
+
+impl ConfigFile {
+    /// Reads and parses the configuration file at `path`.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ConfigFileError> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path).map_err(|err| ConfigFileError {
+            path: Some(path.to_owned()),
+            line: None,
+            kind: ConfigFileErrorKind::Io(err),
+        })?;
+
+        let mut file = Self::parse(&contents).map_err(|mut err| {
+            err.path = Some(path.to_owned());
+            err
+        })?;
+        file.path = Some(path.to_owned());
+        Ok(file)
+    }
+
+    /// Parses configuration in the [file format](ConfigFile#file-format).
+    pub fn parse(contents: &str) -> Result<Self, ConfigFileError> {
+        let mut file = Self::default();
+        let mut section = Section::Settings;
+        let mut recorder_line = None;
+
+        for (i, line) in contents.lines().enumerate() {
+            let error = |kind| ConfigFileError {
+                path: None,
+                line: Some(i + 1),
+                kind,
+            };
+
+            let line = strip_comment(line).trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                let next = match name.trim() {
+                    "format" => Some(Section::Format),
+                    "flight_recorder" => Some(Section::FlightRecorder),
+                    "filters" => Some(Section::Filters),
+                    _ => None,
+                };
+                section = match next {
+                    Some(next) if next > section => next,
+                    _ => {
+                        return Err(error(ConfigFileErrorKind::UnexpectedSection(
+                            name.trim().to_owned(),
+                        )))
+                    }
+                };
+                if section == Section::FlightRecorder {
+                    recorder_line = Some(i + 1);
+                }
+                continue;
+            }
+
+            if section == Section::Filters {
+                file.parse_directive(&unquote(line).map_err(error)?)
+                    .map_err(error)?;
+                continue;
+            }
+
+            let Some((key, value)) = line.split_once('=') else {
+                return Err(error(ConfigFileErrorKind::Syntax(line.to_owned())));
+            };
+            let (key, value) = (key.trim(), unquote(value.trim()).map_err(error)?);
+            file.set(section, key, value).map_err(error)?;
+        }
+
+        // The flight recorder can't be set up from only one of its capacity and level
+        let missing = match (file.recorder_capacity, file.recorder_level) {
+            (Some(_), None) => Some("level"),
+            (None, Some(_)) => Some("capacity"),
+            _ => None,
+        };
+        if let Some(key) = missing {
+            return Err(ConfigFileError {
+                path: None,
+                line: recorder_line,
+                kind: ConfigFileErrorKind::MissingKey(key.to_owned()),
+            });
+        }
+
+        Ok(file)
+    }
+
+    fn set(
+        &mut self,
+        section: Section,
+        key: &str,
+        value: String,
+    ) -> Result<(), ConfigFileErrorKind> {
+        let invalid = |value: String| ConfigFileErrorKind::InvalidValue {
+            key: key.to_owned(),
+            value,
+        };
+
+        match (section, key) {
+            (Section::Settings, "target") => {
+                self.target = Some(match &*value {
+                    "stdout" => Stream::Stdout,
+                    "stderr" => Stream::Stderr,
+                    _ => return Err(invalid(value)),
+                });
+            }
+            (Section::Settings, "write_style") => {
+                self.write_style = Some(match &*value {
+                    "auto" => WriteStyle::Auto,
+                    "always" => WriteStyle::Always,
+                    "never" => WriteStyle::Never,
+                    _ => return Err(invalid(value)),
+                });
+            }
+            (Section::Format, "timestamp") => {
+                self.timestamp = Some(match &*value {
+                    "false" => None,
+                    "seconds" => Some(TimestampPrecision::Seconds),
+                    "millis" => Some(TimestampPrecision::Millis),
+                    "micros" => Some(TimestampPrecision::Micros),
+                    "nanos" => Some(TimestampPrecision::Nanos),
+                    _ => return Err(invalid(value)),
+                });
+            }
+            (Section::Format, "level") => self.level = Some(parse_bool(value).map_err(invalid)?),
+            (Section::Format, "module_path") => {
+                self.module_path = Some(parse_bool(value).map_err(invalid)?);
+            }
+            (Section::Format, "target") => {
+                self.format_target = Some(parse_bool(value).map_err(invalid)?);
+            }
+            (Section::Format, "file") => self.file = Some(parse_bool(value).map_err(invalid)?),
+            (Section::Format, "line_number") => {
+                self.line_number = Some(parse_bool(value).map_err(invalid)?);
+            }
+            (Section::Format, "indent") => {
+                self.indent = Some(match &*value {
+                    "false" => None,
+                    _ => Some(value.parse().map_err(|_| invalid(value))?),
+                });
+            }
+            (Section::Settings, "framing") => {
+                self.framing = Some(match &*value {
+                    "newline" => Framing::Newline,
+                    "nul" => Framing::Nul,
+                    "length_prefixed" => Framing::LengthPrefixed,
+                    "octet_counting" => Framing::OctetCounting,
+                    _ => return Err(invalid(value)),
+                });
+            }
+            (Section::Settings, "buffered") => {
+                self.buffered = Some(value.parse().map_err(|_| invalid(value))?);
+            }
+            (Section::Settings, "flush_interval_ms") => {
+                let millis = value.parse().map_err(|_| invalid(value))?;
+                self.flush_interval = Some(Duration::from_millis(millis));
+            }
+            (Section::Settings, "flush_level") => {
+                self.flush_level = Some(value.parse().map_err(|_| invalid(value))?);
+            }
+            (Section::Settings, "write_errors") => {
+                self.write_errors = Some(match &*value {
+                    "ignore" => OnWriteError::Ignore,
+                    "stderr" => OnWriteError::Fallback(Stream::Stderr),
+                    "stdout" => OnWriteError::Fallback(Stream::Stdout),
+                    _ => OnWriteError::DisableAfter(value.parse().map_err(|_| invalid(value))?),
+                });
+            }
+            (Section::Settings, "log_panics") => {
+                self.log_panics = Some(parse_bool(value).map_err(invalid)?);
+            }
+            (Section::Settings, "log_panic_backtraces") => {
+                self.log_panic_backtraces = Some(parse_bool(value).map_err(invalid)?);
+            }
+            (Section::Format, "suffix") => self.suffix = Some(value),
+            (Section::Format, "sanitize") => {
+                self.sanitize = Some(parse_bool(value).map_err(invalid)?);
+            }
+            (Section::Format, "max_message_len") => {
+                self.max_message_len = Some(match &*value {
+                    "false" => None,
+                    _ => Some(value.parse().map_err(|_| invalid(value))?),
+                });
+            }
+            (Section::FlightRecorder, "capacity") => {
+                self.recorder_capacity = Some(value.parse().map_err(|_| invalid(value))?);
+            }
+            (Section::FlightRecorder, "level") => {
+                self.recorder_level = Some(value.parse().map_err(|_| invalid(value))?);
+            }
+            (Section::FlightRecorder, "trigger") => {
+                self.recorder_trigger = Some(value.parse().map_err(|_| invalid(value))?);
+            }
+            _ => return Err(ConfigFileErrorKind::UnknownKey(key.to_owned())),
+        }
+
+        Ok(())
+    }
+
+    fn parse_directive(&mut self, line: &str) -> Result<(), ConfigFileErrorKind> {
+        env_filter::Builder::new()
+            .try_parse(line)
+            .map_err(ConfigFileErrorKind::InvalidFilter)?;
+
+        let (directive, message_filter) = match line.split_once('/') {
+            Some((directive, message_filter)) => (directive, Some(message_filter)),
+            None => (line, None),
+        };
+        if !directive.trim().is_empty() {
+            self.directives.push(directive.trim().to_owned());
+        }
+        if let Some(message_filter) = message_filter {
+            self.message_filter = Some(message_filter.to_owned());
+        }
+        Ok(())
+    }
+
+    /// The directives from the `[filters]` section, as a single `RUST_LOG` style string.
+    fn filters(&self) -> Option<String> {
+        if self.directives.is_empty() && self.message_filter.is_none() {
+            return None;
+        }
+
+        let mut filters = self.directives.join(",");
+        if let Some(message_filter) = &self.message_filter {
+            filters.push('/');
+            filters.push_str(message_filter);
+        }
+        Some(filters)
+    }
+
+    fn source(&self) -> Source {
+        let name = self
+            .path
+            .as_ref()
+            .map(|path| path.display().to_string())
+            .unwrap_or_default();
+        Source::file(name)
+    }
+
+    /// Applies every setting in the file to `builder`.
+    pub(crate) fn apply(&self, builder: &mut Builder) {
+        if let Some(target) = self.target {
+            builder.target(match target {
+                Stream::Stdout => Target::Stdout,
+                Stream::Stderr => Target::Stderr,
+            });
+        }
+        if let Some(write_style) = self.write_style {
+            builder.write_style(write_style);
+        }
+        if let Some(timestamp) = self.timestamp {
+            builder.format_timestamp(timestamp);
+        }
+        if let Some(level) = self.level {
+            builder.format_level(level);
+        }
+        if let Some(module_path) = self.module_path {
+            builder.format_module_path(module_path);
+        }
+        if let Some(target) = self.format_target {
+            builder.format_target(target);
+        }
+        if let Some(file) = self.file {
+            builder.format_file(file);
+        }
+        if let Some(line_number) = self.line_number {
+            builder.format_line_number(line_number);
+        }
+        if let Some(indent) = self.indent {
+            builder.format_indent(indent);
+        }
+        if let Some(suffix) = &self.suffix {
+            builder.format_owned_suffix(suffix.clone());
+        }
+        if let Some(sanitize) = self.sanitize {
+            builder.format_sanitize(sanitize);
+        }
+        if let Some(max_len) = self.max_message_len {
+            builder.format_max_message_len(max_len);
+        }
+        if let Some(framing) = self.framing {
+            builder.framing(framing);
+        }
+        if let Some(capacity) = self.buffered {
+            builder.buffered(capacity);
+        }
+        if let Some(interval) = self.flush_interval {
+            builder.flush_interval(interval);
+        }
+        if let Some(level) = self.flush_level {
+            builder.flush_level(level);
+        }
+        if let Some(on_error) = self.write_errors {
+            builder.write_error_policy(match on_error {
+                OnWriteError::Ignore => WriteErrorPolicy::Ignore,
+                OnWriteError::Fallback(Stream::Stdout) => {
+                    WriteErrorPolicy::Fallback(Target::Stdout)
+                }
+                OnWriteError::Fallback(Stream::Stderr) => {
+                    WriteErrorPolicy::Fallback(Target::Stderr)
+                }
+                OnWriteError::DisableAfter(failures) => WriteErrorPolicy::DisableAfter(failures),
+            });
+        }
+        if let Some(log) = self.log_panics {
+            builder.log_panics(log);
+        }
+        if let Some(capture) = self.log_panic_backtraces {
+            builder.log_panic_backtraces(capture);
+        }
+        if let (Some(capacity), Some(level)) = (self.recorder_capacity, self.recorder_level) {
+            builder.flight_recorder(capacity, level);
+        }
+        if let Some(trigger) = self.recorder_trigger {
+            builder.flight_recorder_trigger(trigger);
+        }
+        if let Some(filters) = self.filters() {
+            builder.parse_filters(&filters);
+        }
+    }
+
+    /// Adds the filters and write style in the file to `config`.
+    pub(crate) fn add_to(&self, config: &mut Config) {
+        if let Some(filters) = self.filters() {
+            config.parse_filters(self.source(), &filters);
+        }
+        if let Some(write_style) = self.write_style {
+            config.write_style(self.source(), write_style);
+        }
+    }
+}
+
+fn strip_comment(line: &str) -> &str {
+    let mut quoted = false;
+    let mut escaped = false;
+    let mut after_space = true;
+    for (i, c) in line.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            '#' if !quoted && after_space => return &line[..i],
+            _ => {}
+        }
+        after_space = c.is_whitespace();
+    }
+    line
+}
+
+fn unquote(value: &str) -> Result<String, ConfigFileErrorKind> {
+    let Some(quoted) = value.strip_prefix('"') else {
+        return Ok(value.to_owned());
+    };
+    let invalid = || ConfigFileErrorKind::Syntax(value.to_owned());
+    let quoted = quoted.strip_suffix('"').ok_or_else(invalid)?;
+
+    let mut unquoted = String::with_capacity(quoted.len());
+    let mut chars = quoted.chars();
+    while let Some(c) = chars.next() {
+        unquoted.push(match c {
+            '\\' => match chars.next() {
+                Some('n') => '\n',
+                Some('r') => '\r',
+                Some('t') => '\t',
+                Some('0') => '\0',
+                Some('\\') => '\\',
+                Some('"') => '"',
+                _ => return Err(invalid()),
+            },
+            '"' => return Err(invalid()),
+            c => c,
+        });
+    }
+    Ok(unquoted)
+}
+
+fn parse_bool(value: String) -> Result<bool, String> {
+    match &*value {
+        "true" => Ok(true),
+        "false" => Ok(false),
+        _ => Err(value),
+    }
+}
+
+/// An error loading a [`ConfigFile`].
+#[derive(Debug)]
+pub struct ConfigFileError {
+    path: Option<PathBuf>,
+    line: Option<usize>,
+    kind: ConfigFileErrorKind,
+}
+
+impl ConfigFileError {
+    /// The path of the file, if it was [loaded][ConfigFile::load] from one.
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// The 1-based number of the offending line, if the error is about a specific line.
+    pub fn line(&self) -> Option<usize> {
+        self.line
+    }
+
+    /// What went wrong.
+    pub fn kind(&self) -> &ConfigFileErrorKind {
+        &self.kind
+    }
+}
+
+impl fmt::Display for ConfigFileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.path, self.line) {
+            (Some(path), Some(line)) => write!(f, "{}:{line}: ", path.display())?,
+            (Some(path), None) => write!(f, "{}: ", path.display())?,
+            (None, Some(line)) => write!(f, "line {line}: ")?,
+            (None, None) => {}
+        }
+        self.kind.fmt(f)
+    }
+}
+
+impl std::error::Error for ConfigFileError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match &self.kind {
+            ConfigFileErrorKind::Io(err) => Some(err),
+            ConfigFileErrorKind::InvalidFilter(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+/// The kind of a [`ConfigFileError`].
+#[non_exhaustive]
+#[derive(Debug)]
+pub enum ConfigFileErrorKind {
+    /// The file couldn't be read.
+    Io(io::Error),
+    /// The line isn't a section header, a `key = value` setting or a well-formed value.
+    Syntax(String),
+    /// A section header that is unknown or out of order.
+    UnexpectedSection(String),
+    /// A key that doesn't belong in its section.
+    UnknownKey(String),
+    /// A key that its section needs, like the `level` of the `[flight_recorder]`.
+    MissingKey(String),
+    /// A value that isn't valid for its key.
+    InvalidValue {
+        /// The key of the setting.
+        key: String,
+        /// The invalid value.
+        value: String,
+    },
+    /// A directive in the `[filters]` section that can't be parsed.
+    InvalidFilter(env_filter::ParseError),
+}
+
+impl fmt::Display for ConfigFileErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => err.fmt(f),
+            Self::Syntax(line) => write!(f, "invalid syntax `{line}`"),
+            Self::UnexpectedSection(name) => write!(f, "unexpected section `[{name}]`"),
+            Self::UnknownKey(key) => write!(f, "unknown key `{key}`"),
+            Self::MissingKey(key) => write!(f, "missing key `{key}`"),
+            Self::InvalidValue { key, value } => write!(f, "invalid value `{value}` for `{key}`"),
+            Self::InvalidFilter(err) => err.fmt(f),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use log::LevelFilter;
+
+    #[test]
+    fn parse_all_settings() {
+        let file = ConfigFile::parse(
+            r#"
+# Settings
+target = stdout
+write_style = "never"   # no colors
+framing = octet_counting
+buffered = 4096
+flush_interval_ms = 250
+flush_level = warn
+write_errors = 3
+log_panics = true
+log_panic_backtraces = false
+
+[format]
+timestamp = "millis"
+level = false
+module_path = true
+target = false
+file = true
+line_number = true
+indent = false
+suffix = "\r\n# not a comment"
+sanitize = true
+max_message_len = 64
+
+[flight_recorder]
+capacity = 100
+level = debug
+trigger = warn
+
+[filters]
+info
+hyper=debug, tokio=warn  # comment
+/request
+"#,
+        )
+        .unwrap();
+
+        assert!(matches!(file.target, Some(Stream::Stdout)));
+        assert_eq!(file.write_style, Some(WriteStyle::Never));
+        assert!(matches!(
+            file.timestamp,
+            Some(Some(TimestampPrecision::Millis))
+        ));
+        assert_eq!(file.level, Some(false));
+        assert_eq!(file.module_path, Some(true));
+        assert_eq!(file.format_target, Some(false));
+        assert_eq!(file.file, Some(true));
+        assert_eq!(file.line_number, Some(true));
+        assert_eq!(file.indent, Some(None));
+        assert_eq!(file.suffix.as_deref(), Some("\r\n# not a comment"));
+        assert_eq!(file.sanitize, Some(true));
+        assert_eq!(file.max_message_len, Some(Some(64)));
+        assert_eq!(file.framing, Some(Framing::OctetCounting));
+        assert_eq!(file.buffered, Some(4096));
+        assert_eq!(file.flush_interval, Some(Duration::from_millis(250)));
+        assert_eq!(file.flush_level, Some(Level::Warn));
+        assert!(matches!(
+            file.write_errors,
+            Some(OnWriteError::DisableAfter(3))
+        ));
+        assert_eq!(file.log_panics, Some(true));
+        assert_eq!(file.log_panic_backtraces, Some(false));
+        assert_eq!(file.recorder_capacity, Some(100));
+        assert_eq!(file.recorder_level, Some(LevelFilter::Debug));
+        assert_eq!(file.recorder_trigger, Some(Level::Warn));
+        assert_eq!(
+            file.filters().as_deref(),
+            Some("info,hyper=debug, tokio=warn/request")
+        );
+    }
+
+    #[test]
+    fn parse_hash_in_filters() {
+        let file = ConfigFile::parse("[filters]\ninfo\n/issue#[0-9]+  # comment\n").unwrap();
+        assert_eq!(file.filters().as_deref(), Some("info/issue#[0-9]+"));
+
+        let file = ConfigFile::parse("[filters]\n\"/issue #[0-9]+\"  # comment\n").unwrap();
+        assert_eq!(file.filters().as_deref(), Some("/issue #[0-9]+"));
+    }
+
+    #[test]
+    fn parse_empty() {
+        let file = ConfigFile::parse("# nothing here\n\n[filters]\n").unwrap();
+        assert!(file.target.is_none());
+        assert!(file.filters().is_none());
+    }
+
+    #[test]
+    fn parse_errors_point_at_line() {
+        let cases = [
+            (
+                "target = stdout\ntarget = file\n",
+                "line 2: invalid value `file` for `target`",
+            ),
+            (
+                "[format]\nindent = -1",
+                "line 2: invalid value `-1` for `indent`",
+            ),
+            ("colors = true", "line 1: unknown key `colors`"),
+            (
+                "[format]\nwrite_style = never",
+                "line 2: unknown key `write_style`",
+            ),
+            (
+                "\n\n[filters]\n[format]",
+                "line 4: unexpected section `[format]`",
+            ),
+            ("[output]", "line 1: unexpected section `[output]`"),
+            (
+                "[flight_recorder]\ncapacity = 10\n[format]",
+                "line 3: unexpected section `[format]`",
+            ),
+            (
+                "log_panics = yes",
+                "line 1: invalid value `yes` for `log_panics`",
+            ),
+            (
+                "write_errors = often",
+                "line 1: invalid value `often` for `write_errors`",
+            ),
+            (
+                "\n[flight_recorder]\ncapacity = 10\n",
+                "line 2: missing key `level`",
+            ),
+            ("target stdout", "line 1: invalid syntax `target stdout`"),
+            (
+                "[format]\nsuffix = \"\\q\"",
+                "line 2: invalid syntax `\"\\q\"`",
+            ),
+            (
+                "[filters]\ninfo\nhyper=loud",
+                "line 3: error parsing logger filter: invalid logging spec 'loud'",
+            ),
+        ];
+
+        for (contents, expected) in cases {
+            let err = ConfigFile::parse(contents).unwrap_err();
+            assert_eq!(err.to_string(), expected, "for {contents:?}");
+        }
+    }
+
+    #[test]
+    fn load_missing_file() {
+        let err = ConfigFile::load("/nonexistent/logging.conf").unwrap_err();
+        assert!(matches!(err.kind(), ConfigFileErrorKind::Io(_)));
+        assert_eq!(err.path(), Some(Path::new("/nonexistent/logging.conf")));
+        assert_eq!(err.line(), None);
+    }
+
+    #[test]
+    fn load_reports_path() {
+        let path = std::env::temp_dir().join("env_logger_load_reports_path.conf");
+        std::fs::write(&path, "[filters]\nwarn\nhyper=loud\n").unwrap();
+
+        let err = ConfigFile::load(&path).unwrap_err();
+        assert_eq!(err.path(), Some(&*path));
+        assert_eq!(err.line(), Some(3));
+        assert!(err
+            .to_string()
+            .starts_with(&format!("{}:3: ", path.display())));
+
+        std::fs::write(&path, "[filters]\nwarn\nhyper=debug\n").unwrap();
+        let file = ConfigFile::load(&path).unwrap();
+        let mut config = Config::new();
+        config.add_file(&file);
+        assert_eq!(
+            config.explain("hyper").unwrap().source(),
+            &Source::file(path.display().to_string())
+        );
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn apply_to_builder() {
+        let file = ConfigFile::parse("[filters]\nwarn\nhyper=debug\n").unwrap();
+        let logger = Builder::new()
+            .filter_level(LevelFilter::Trace)
+            .apply_config_file(&file)
+            .build();
+        assert_eq!(logger.filter(), LevelFilter::Debug);
+    }
+
+    #[test]
+    fn apply_format_and_output_settings() {
+        use std::sync::{Arc, Mutex};
+
+        use log::Log as _;
+
+        let file = ConfigFile::parse(
+            "write_style = never\nframing = nul\n\n[format]\nlevel = false\ntarget = false\ntimestamp = false\nsanitize = true\nmax_message_len = 8\n",
+        )
+        .unwrap();
+
+        let records = Arc::new(Mutex::new(Vec::new()));
+        let logger = {
+            let records = records.clone();
+            Builder::new()
+                .filter_level(LevelFilter::Info)
+                .target(Target::Callback(Box::new(move |bytes, _| {
+                    records.lock().unwrap().push(bytes.to_vec());
+                })))
+                .apply_config_file(&file)
+                .build()
+        };
+        logger.log(
+            &log::Record::builder()
+                .level(Level::Info)
+                .args(format_args!("a\nb and more"))
+                .build(),
+        );
+
+        assert_eq!(*records.lock().unwrap(), [b"a\\nb and... [truncated]\0"]);
+    }
+}
@@ -0,0 +1,516 @@
+//! Configuration collected from several sources.
+//!
+//! [`Builder`] applies configuration in the order its methods are called, so
+//! [`Builder::parse_env`] overrides any filters set before it.
+//! When configuration comes from code defaults, a config file, environment
+//! variables and command-line flags, a [`Config`] can collect all of them first,
+//! with each setting tagged by the [`Source`] it came from.
+//!
+//! Sources are ranked by their [`Layer`]: a setting from a higher layer always
+//! overrides the same setting from a lower layer, regardless of the order they
+//! were added in. Within a layer, the setting added last wins.
+//!
+//! # Examples
+//!
+//! ```
+//! use env_logger::config::{Config, Source};
+//! use env_logger::Builder;
+//! use log::LevelFilter;
+//!
+//! let mut config = Config::new();
+//!
+//! // Command-line flags take precedence, even over `RUST_LOG`
+//! config.parse_filters(Source::cli("--log"), "hyper=debug");
+//! config.parse_env(env_logger::Env::default());
+//! config.filter(Source::code("main"), None, LevelFilter::Info);
+//! config.filter(Source::code("main"), Some("hyper"), LevelFilter::Warn);
+//!
+//! // Prints `hyper=debug from --log (cli), overriding hyper=warn from main (code)`
+//! println!("{}", config.explain("hyper::client").unwrap());
+//!
+//! Builder::new().apply_config(&config).init();
+//! ```
+
+mod file;
+
+use std::fmt;
+
+use log::LevelFilter;
+
+use crate::logger::{Builder, Env};
+use crate::WriteStyle;
+
+pub use file::{ConfigFile, ConfigFileError, ConfigFileErrorKind};
+
+/// The precedence of a [`Source`].
+///
+/// Layers are ordered from lowest to highest precedence.
+#[non_exhaustive]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Layer {
+    /// Defaults set in code.
+    Code,
+    /// A configuration file.
+    File,
+    /// An environment variable.
+    Env,
+    /// A command-line flag.
+    Cli,
+}
+
+impl fmt::Display for Layer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Code => "code",
+            Self::File => "file",
+            Self::Env => "env",
+            Self::Cli => "cli",
+        })
+    }
+}
+
+/// Where a setting came from.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Source {
+    layer: Layer,
+    name: String,
+}
+
+impl Source {
+    /// A source named `name` in the given layer.
+    pub fn new(layer: Layer, name: impl Into<String>) -> Self {
+        Self {
+            layer,
+            name: name.into(),
+        }
+    }
+
+    /// Defaults set in code, such as in `main`.
+    pub fn code(name: impl Into<String>) -> Self {
+        Self::new(Layer::Code, name)
+    }
+
+    /// A configuration file, named by its path.
+    pub fn file(path: impl Into<String>) -> Self {
+        Self::new(Layer::File, path)
+    }
+
+    /// An environment variable, named by the variable.
+    pub fn env(var: impl Into<String>) -> Self {
+        Self::new(Layer::Env, var)
+    }
+
+    /// A command-line flag, named by the flag.
+    pub fn cli(flag: impl Into<String>) -> Self {
+        Self::new(Layer::Cli, flag)
+    }
+
+    /// The precedence of this source.
+    pub fn layer(&self) -> Layer {
+        self.layer
+    }
+
+    /// The name of this source.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.name, self.layer)
+    }
+}
+
+/// A setting along with where it came from, and what it overrides.
+///
+/// The [`Display`][fmt::Display] implementation renders it as a single line such as
+/// `hyper=debug from RUST_LOG (env), overriding hyper=info from /etc/app.conf (file)`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Origin<'a> {
+    setting: String,
+    source: &'a Source,
+    overridden: Vec<(String, &'a Source)>,
+}
+
+impl<'a> Origin<'a> {
+    /// The effective setting, as it would be written in `RUST_LOG` for filters.
+    pub fn setting(&self) -> &str {
+        &self.setting
+    }
+
+    /// Where the effective setting came from.
+    pub fn source(&self) -> &'a Source {
+        self.source
+    }
+
+    /// The settings it overrides, from highest to lowest precedence.
+    pub fn overridden(&self) -> impl Iterator<Item = (&str, &'a Source)> + '_ {
+        self.overridden
+            .iter()
+            .map(|(setting, source)| (setting.as_str(), *source))
+    }
+}
+
+impl fmt::Display for Origin<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} from {}", self.setting, self.source)?;
+        for (setting, source) in &self.overridden {
+            write!(f, ", overriding {setting} from {source}")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Setting<T> {
+    value: T,
+    source: Source,
+}
+
+type Directive = (Option<String>, LevelFilter);
+
+/// Logging configuration collected from several [`Source`]s.
+///
+/// See the [module documentation](self) for details.
+#[derive(Clone, Debug, Default)]
+pub struct Config {
+    // Every setting is kept, in the order added, so overridden ones can be reported
+    directives: Vec<Setting<Directive>>,
+    message_filters: Vec<Setting<String>>,
+    write_styles: Vec<Setting<WriteStyle>>,
+}
+
+impl Config {
+    /// An empty configuration.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a filter directive.
+    ///
+    /// The given module (if any) will log at most the specified level provided.
+    /// If no module is provided then the filter will apply to all log messages.
+    pub fn filter(
+        &mut self,
+        source: Source,
+        module: Option<&str>,
+        level: LevelFilter,
+    ) -> &mut Self {
+        self.directives.push(Setting {
+            value: (module.map(ToOwned::to_owned), level),
+            source,
+        });
+        self
+    }
+
+    /// Parses the directives string in the same form as the `RUST_LOG`
+    /// environment variable.
+    ///
+    /// Like [`Builder::parse_filters`], invalid directives are reported on stderr and ignored.
+    pub fn parse_filters(&mut self, source: Source, filters: &str) -> &mut Self {
+        let mut parsed = env_filter::Builder::new();
+        parsed.parse(filters);
+
+        for (module, level) in parsed.directives() {
+            self.filter(source.clone(), module, level);
+        }
+        if parsed.build().has_message_filter() {
+            if let Some((_, message_filter)) = filters.split_once('/') {
+                self.message_filters.push(Setting {
+                    value: message_filter.to_owned(),
+                    source,
+                });
+            }
+        }
+        self
+    }
+
+    /// Sets whether or not styles will be written.
+    pub fn write_style(&mut self, source: Source, write_style: WriteStyle) -> &mut Self {
+        self.write_styles.push(Setting {
+            value: write_style,
+            source,
+        });
+        self
+    }
+
+    /// Parses whether or not to write styles in the same form as the `RUST_LOG_STYLE`
+    /// environment variable.
+    pub fn parse_write_style(&mut self, source: Source, write_style: &str) -> &mut Self {
+        self.write_style(source, crate::writer::parse_write_style(write_style))
+    }
+
+    /// Adds the configuration from the environment.
+    ///
+    /// Values read from a variable are in the [`Layer::Env`] layer, named by the variable.
+    /// Defaults used when a variable isn't set are in the [`Layer::Code`] layer.
+    pub fn parse_env<'a, E>(&mut self, env: E) -> &mut Self
+    where
+        E: Into<Env<'a>>,
+    {
+        let env = env.into();
+
+        if let Some((s, source)) = env.get_filter_with_source() {
+            self.parse_filters(source, &s);
+        }
+
+        if let Some((s, source)) = env.get_write_style_with_source() {
+            self.parse_write_style(source, &s);
+        }
+
+        self
+    }
+
+    /// Adds the filters and write style from a configuration file.
+    ///
+    /// Values are in the [`Layer::File`] layer, named by the path the file was loaded from.
+    pub fn add_file(&mut self, file: &ConfigFile) -> &mut Self {
+        file.add_to(self);
+        self
+    }
+
+    /// Explains which directive applies to `target`, and where it came from.
+    ///
+    /// Returns `None` if no directive matches `target`.
+    pub fn explain(&self, target: &str) -> Option<Origin<'_>> {
+        self.directive_origins()
+            .filter(|(module, _)| target.starts_with(module.unwrap_or_default()))
+            .max_by_key(|(module, _)| module.map(|m| m.len()).unwrap_or(0))
+            .map(|(_, origin)| origin)
+    }
+
+    /// Explains which message filter applies, and where it came from.
+    pub fn explain_message_filter(&self) -> Option<Origin<'_>> {
+        origin(self.message_filters.iter(), |filter| format!("/{filter}"))
+    }
+
+    /// Explains which write style applies, and where it came from.
+    pub fn explain_write_style(&self) -> Option<Origin<'_>> {
+        origin(self.write_styles.iter(), |write_style| {
+            format!("style={}", write_style_name(*write_style))
+        })
+    }
+
+    /// The effective directive for each module, in the order they were first added.
+    fn directive_origins(&self) -> impl Iterator<Item = (Option<&str>, Origin<'_>)> {
+        let mut modules = Vec::new();
+        for setting in &self.directives {
+            let module = setting.value.0.as_deref();
+            if !modules.contains(&module) {
+                modules.push(module);
+            }
+        }
+
+        modules.into_iter().filter_map(|module| {
+            let settings = self
+                .directives
+                .iter()
+                .filter(move |setting| setting.value.0.as_deref() == module);
+            origin(settings, |(module, level)| {
+                directive_spec(module.as_deref(), *level)
+            })
+            .map(|origin| (module, origin))
+        })
+    }
+
+    /// Applies the effective settings to `builder`.
+    pub(crate) fn apply(&self, builder: &mut Builder) {
+        for setting in self
+            .directives
+            .iter()
+            .filter_map(|s| winner(&self.directives, s))
+        {
+            builder.filter(setting.value.0.as_deref(), setting.value.1);
+        }
+        if let Some(message_filter) = effective(&self.message_filters) {
+            builder.parse_filters(&format!("/{}", message_filter.value));
+        }
+        if let Some(write_style) = effective(&self.write_styles) {
+            builder.write_style(write_style.value);
+        }
+    }
+}
+
+impl fmt::Display for Config {
+    /// Lists each effective setting and where it came from, one per line.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (_, origin) in self.directive_origins() {
+            writeln!(f, "{origin}")?;
+        }
+        if let Some(origin) = self.explain_message_filter() {
+            writeln!(f, "{origin}")?;
+        }
+        if let Some(origin) = self.explain_write_style() {
+            writeln!(f, "{origin}")?;
+        }
+        Ok(())
+    }
+}
+
+/// The setting with the highest precedence, preferring the one added last within a layer.
+fn effective<T>(settings: &[Setting<T>]) -> Option<&Setting<T>> {
+    settings
+        .iter()
+        .enumerate()
+        .max_by_key(|(i, setting)| (setting.source.layer, *i))
+        .map(|(_, setting)| setting)
+}
+
+/// `setting` if it is the effective directive for its module.
+fn winner<'a>(
+    directives: &'a [Setting<Directive>],
+    setting: &'a Setting<Directive>,
+) -> Option<&'a Setting<Directive>> {
+    let winner = directives
+        .iter()
+        .enumerate()
+        .filter(|(_, other)| other.value.0 == setting.value.0)
+        .max_by_key(|(i, other)| (other.source.layer, *i))
+        .map(|(_, winner)| winner)?;
+    std::ptr::eq(winner, setting).then_some(setting)
+}
+
+fn origin<'a, T: 'a>(
+    settings: impl Iterator<Item = &'a Setting<T>>,
+    describe: impl Fn(&T) -> String,
+) -> Option<Origin<'a>> {
+    let mut settings = settings.enumerate().collect::<Vec<_>>();
+    settings.sort_by_key(|(i, setting)| std::cmp::Reverse((setting.source.layer, *i)));
+
+    let mut settings = settings.into_iter().map(|(_, setting)| setting);
+    let effective = settings.next()?;
+    Some(Origin {
+        setting: describe(&effective.value),
+        source: &effective.source,
+        overridden: settings
+            .map(|setting| (describe(&setting.value), &setting.source))
+            .collect(),
+    })
+}
+
+fn directive_spec(module: Option<&str>, level: LevelFilter) -> String {
+    let level = level.as_str().to_ascii_lowercase();
+    match module {
+        Some(module) => format!("{module}={level}"),
+        None => level,
+    }
+}
+
+fn write_style_name(write_style: WriteStyle) -> &'static str {
+    match write_style {
+        WriteStyle::Auto => "auto",
+        WriteStyle::Always => "always",
+        WriteStyle::Never => "never",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn higher_layer_overrides_regardless_of_order() {
+        let mut config = Config::new();
+        config
+            .parse_filters(Source::cli("--log"), "hyper=debug")
+            .parse_filters(Source::env("RUST_LOG"), "info,hyper=trace")
+            .filter(Source::code("main"), Some("hyper"), LevelFilter::Warn);
+
+        let origin = config.explain("hyper::client").unwrap();
+        assert_eq!(origin.setting(), "hyper=debug");
+        assert_eq!(origin.source(), &Source::cli("--log"));
+        assert_eq!(
+            origin.to_string(),
+            "hyper=debug from --log (cli), overriding hyper=trace from RUST_LOG (env), \
+             overriding hyper=warn from main (code)"
+        );
+
+        let origin = config.explain("tokio").unwrap();
+        assert_eq!(origin.to_string(), "info from RUST_LOG (env)");
+    }
+
+    #[test]
+    fn last_wins_within_layer() {
+        let mut config = Config::new();
+        config
+            .parse_filters(Source::env("RUST_LOG"), "hyper=debug")
+            .parse_filters(Source::env("MYAPP_LOG"), "hyper=info");
+
+        assert_eq!(
+            config.explain("hyper").unwrap().to_string(),
+            "hyper=info from MYAPP_LOG (env), overriding hyper=debug from RUST_LOG (env)"
+        );
+    }
+
+    #[test]
+    fn explain_longest_match() {
+        let mut config = Config::new();
+        config
+            .parse_filters(Source::file("app.conf"), "warn,hyper=info")
+            .parse_filters(Source::env("RUST_LOG"), "hyper::client=trace");
+
+        assert_eq!(
+            config.explain("hyper::client").unwrap().setting(),
+            "hyper::client=trace"
+        );
+        assert_eq!(
+            config.explain("hyper::server").unwrap().setting(),
+            "hyper=info"
+        );
+        assert_eq!(config.explain("tokio").unwrap().setting(), "warn");
+        assert_eq!(Config::new().explain("tokio"), None);
+    }
+
+    #[test]
+    fn display_lists_effective_settings() {
+        let mut config = Config::new();
+        config
+            .parse_filters(Source::file("app.conf"), "warn,hyper=info/foo")
+            .parse_filters(Source::cli("-v"), "debug")
+            .write_style(Source::code("main"), WriteStyle::Never)
+            .parse_write_style(Source::env("RUST_LOG_STYLE"), "always");
+
+        assert_eq!(
+            config.to_string(),
+            "debug from -v (cli), overriding warn from app.conf (file)
+hyper=info from app.conf (file)
+/foo from app.conf (file)
+style=always from RUST_LOG_STYLE (env), overriding style=never from main (code)
+"
+        );
+    }
+
+    #[test]
+    fn parse_env_sources() {
+        std::env::set_var("config_parse_env_sources", "hyper=debug");
+        std::env::remove_var("config_parse_env_sources_style");
+
+        let mut config = Config::new();
+        config.parse_env(
+            Env::new()
+                .filter("config_parse_env_sources")
+                .write_style_or("config_parse_env_sources_style", "never"),
+        );
+
+        assert_eq!(
+            config.explain("hyper").unwrap().source(),
+            &Source::env("config_parse_env_sources")
+        );
+        assert_eq!(
+            config.explain_write_style().unwrap().source(),
+            &Source::code("config_parse_env_sources_style")
+        );
+    }
+
+    #[test]
+    fn apply_effective_settings() {
+        let mut config = Config::new();
+        config
+            .parse_filters(Source::cli("--log"), "hyper=debug")
+            .parse_filters(Source::env("RUST_LOG"), "info,hyper=trace");
+
+        let logger = Builder::new().apply_config(&config).build();
+        assert_eq!(logger.filter(), LevelFilter::Debug);
+    }
+}
@@ -0,0 +1,204 @@
+//! Appending records to files.
+//!
+//! An [`AppendFile`] appends records to a file that other processes may be appending to
+//! as well, like several workers sharing one log file. It's used as a [`Target::Pipe`]:
+//!
+//! ```no_run
+//! use env_logger::file::AppendFile;
+//! use env_logger::Target;
+//!
+//! let file = AppendFile::open("/var/log/worker.log")?;
+//!
+//! env_logger::Builder::from_default_env()
+//!     .target(Target::Pipe(Box::new(file)))
+//!     .init();
+//! # Ok::<(), std::io::Error>(())
+//! ```
+//!
+//! # Atomicity
+//!
+//! The file is opened in append mode (`O_APPEND`), and each record is written with a
+//! single `write` call. On Linux, each such call moves to the end of the file and writes
+//! there in one step, and local filesystems like ext4 and XFS don't interleave it with
+//! concurrent appends to the same file, so records from different processes stay whole.
+//!
+//! A `write` call may still write only part of a record, like when the disk is full or
+//! the record is larger than the kernel writes at once (about 2 GiB). The rest of the
+//! record is then written with more calls, so it's not lost but may be interleaved with
+//! other records. Network filesystems like NFS don't guarantee atomic appends at all.
+//!
+//! # Reopening
+//!
+//! Tools like `logrotate` move the file aside and create a new one at its path, but the
+//! process keeps writing to the file it opened. A [`ReopenHandle`] makes the target open
+//! the path again before the next record:
+//!
+//! ```no_run
+//! use env_logger::file::AppendFile;
+//! use env_logger::Target;
+//!
+//! let file = AppendFile::open("/var/log/worker.log")?;
+//! let reopen = file.reopen_handle();
+//!
+//! env_logger::Builder::from_default_env()
+//!     .target(Target::Pipe(Box::new(file)))
+//!     .init();
+//!
+//! // Later, maybe in response to an admin request
+//! reopen.reopen();
+//! # Ok::<(), std::io::Error>(())
+//! ```
+//!
+//! With the `signal` feature, [`ReopenHandle::watch_signals`] reopens it on `SIGHUP`, as
+//! sent by the `postrotate` script of a `logrotate` configuration.
+//!
+//! [`Target::Pipe`]: crate::Target::Pipe
+
+#[cfg(all(unix, feature = "signal"))]
+mod signal;
+
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Appends records to a file, writing each one in a single call when possible.
+///
+/// Each write to the target is one record, which is how the logger writes to it.
+///
+/// See the [module documentation][self] for more details.
+#[derive(Debug)]
+pub struct AppendFile {
+    path: PathBuf,
+    file: File,
+    reopen: Arc<AtomicBool>,
+}
+
+impl AppendFile {
+    /// Opens the file at `path` for appending, creating it if it doesn't exist.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref().to_owned();
+        let file = open(&path)?;
+        Ok(Self {
+            path,
+            file,
+            reopen: Default::default(),
+        })
+    }
+
+    /// Returns a handle to reopen the file.
+    pub fn reopen_handle(&self) -> ReopenHandle {
+        ReopenHandle {
+            reopen: self.reopen.clone(),
+        }
+    }
+
+    /// Opens the path again if requested, keeping the current file if that fails.
+    fn reopen_if_requested(&mut self) {
+        if !self.reopen.swap(false, Ordering::Relaxed) {
+            return;
+        }
+
+        match open(&self.path) {
+            Ok(file) => self.file = file,
+            // Try again before the next record
+            Err(_) => self.reopen.store(true, Ordering::Relaxed),
+        }
+    }
+}
+
+fn open(path: &Path) -> io::Result<File> {
+    OpenOptions::new().create(true).append(true).open(path)
+}
+
+impl io::Write for AppendFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.reopen_if_requested();
+
+        let written = self.file.write(buf)?;
+        if written < buf.len() && written != 0 {
+            // Don't let the caller retry a partial record as a new one
+            self.file.write_all(&buf[written..])?;
+            return Ok(buf.len());
+        }
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+/// A handle to reopen an [`AppendFile`].
+///
+/// Handles are cheap to clone, and every clone reopens the same file.
+#[derive(Clone, Debug)]
+pub struct ReopenHandle {
+    reopen: Arc<AtomicBool>,
+}
+
+impl ReopenHandle {
+    /// Opens the path of the file again, before the next record is written.
+    ///
+    /// The new file is opened before the current one is closed, and records keep going to
+    /// the current file until the new one is open. If opening fails, it's tried again for
+    /// each record.
+    pub fn reopen(&self) {
+        self.reopen.store(true, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write as _;
+    use std::path::PathBuf;
+
+    use super::AppendFile;
+
+    fn temp_path(line: u32) -> PathBuf {
+        std::env::temp_dir().join(format!(
+            "env_logger-append-{}-{line}.log",
+            std::process::id()
+        ))
+    }
+
+    #[test]
+    fn append_to_existing_file() {
+        let path = temp_path(line!());
+        std::fs::write(&path, "existing\n").unwrap();
+
+        let mut first = AppendFile::open(&path).unwrap();
+        let mut second = AppendFile::open(&path).unwrap();
+        first.write_all(b"first\n").unwrap();
+        second.write_all(b"second\n").unwrap();
+        first.write_all(b"third\n").unwrap();
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(contents, "existing\nfirst\nsecond\nthird\n");
+    }
+
+    #[test]
+    fn reopen_rotated_file() {
+        let path = temp_path(line!());
+        let rotated = path.with_extension("log.1");
+        let _ = std::fs::remove_file(&path);
+
+        let mut file = AppendFile::open(&path).unwrap();
+        let reopen = file.reopen_handle();
+        file.write_all(b"before\n").unwrap();
+
+        std::fs::rename(&path, &rotated).unwrap();
+        file.write_all(b"rotated\n").unwrap();
+        reopen.reopen();
+        file.write_all(b"after\n").unwrap();
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        let rotated_contents = std::fs::read_to_string(&rotated).unwrap();
+        let _ = std::fs::remove_file(&path);
+        let _ = std::fs::remove_file(&rotated);
+        assert_eq!(rotated_contents, "before\nrotated\n");
+        assert_eq!(contents, "after\n");
+    }
+}
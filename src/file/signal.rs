@@ -0,0 +1,63 @@
+use std::io;
+
+use signal_hook::consts::SIGHUP;
+
+use crate::file::ReopenHandle;
+use crate::reload::SignalWatcher;
+
+impl ReopenHandle {
+    /// Reopens the file on `SIGHUP`.
+    ///
+    /// Signals are handled on a background thread until the returned [`SignalWatcher`] is
+    /// dropped or [detached][SignalWatcher::detach].
+    ///
+    /// # Errors
+    ///
+    /// Fails if the signal handler can't be registered.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use env_logger::file::AppendFile;
+    ///
+    /// let file = AppendFile::open("/var/log/worker.log").unwrap();
+    /// // `kill -HUP <pid>` now reopens the file
+    /// file.reopen_handle().watch_signals().unwrap().detach();
+    /// ```
+    pub fn watch_signals(&self) -> io::Result<SignalWatcher> {
+        let handle = self.clone();
+        SignalWatcher::spawn(&[SIGHUP], move |_| handle.reopen())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::Ordering;
+    use std::time::{Duration, Instant};
+
+    use signal_hook::consts::SIGHUP;
+    use signal_hook::low_level::raise;
+
+    use crate::file::AppendFile;
+
+    #[test]
+    fn watch_signals_reopens() {
+        let path = std::env::temp_dir().join(format!(
+            "env_logger-reopen-signal-{}.log",
+            std::process::id()
+        ));
+        let file = AppendFile::open(&path).unwrap();
+        let handle = file.reopen_handle();
+        let watcher = handle.watch_signals().unwrap();
+
+        raise(SIGHUP).unwrap();
+        let start = Instant::now();
+        while !handle.reopen.load(Ordering::Relaxed) {
+            assert!(start.elapsed() < Duration::from_secs(5), "not reopened");
+            std::thread::sleep(Duration::from_millis(5));
+        }
+
+        drop(watcher);
+        let _ = std::fs::remove_file(&path);
+    }
+}
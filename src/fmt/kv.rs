@@ -2,7 +2,7 @@ use std::io::{self, Write};
 
 #[cfg(feature = "color")]
 use super::WriteStyle;
-use super::{Formatter, StyledValue};
+use super::{Formatter, Sanitized, StyledValue};
 #[cfg(feature = "color")]
 use anstyle::Style;
 use log::kv::{Error, Key, Source, Value, VisitSource};
@@ -43,7 +43,11 @@ struct DefaultVisitSource<'a>(&'a mut Formatter);
 
 impl<'kvs> VisitSource<'kvs> for DefaultVisitSource<'_> {
     fn visit_pair(&mut self, key: Key<'_>, value: Value<'kvs>) -> Result<(), Error> {
-        write!(self.0, " {}={}", self.style_key(key), value)?;
+        if self.0.sanitize {
+            write!(self.0, " {}={}", self.style_key(key), Sanitized(value))?;
+        } else {
+            write!(self.0, " {}={}", self.style_key(key), value)?;
+        }
         Ok(())
     }
 }
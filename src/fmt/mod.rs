@@ -57,6 +57,7 @@
 //! [`Write`]: std::io::Write
 //! [`Builder::format_key_values`]: crate::Builder::format_key_values
 
+use std::borrow::Cow;
 use std::cell::RefCell;
 use std::fmt::Display;
 use std::io::prelude::Write;
@@ -71,6 +72,8 @@ use log::Record;
 mod humantime;
 #[cfg(feature = "kv")]
 mod kv;
+mod pattern;
+mod sanitize;
 
 #[cfg(feature = "color")]
 pub use anstyle as style;
@@ -79,9 +82,11 @@ pub use anstyle as style;
 pub use self::humantime::Timestamp;
 #[cfg(feature = "kv")]
 pub use self::kv::*;
-pub use crate::writer::Target;
+pub use self::pattern::{PatternError, PatternFormat};
 pub use crate::writer::WriteStyle;
+pub use crate::writer::{RecordMeta, Target};
 
+use self::sanitize::{write_truncated, Sanitized};
 use crate::writer::{Buffer, Writer};
 
 /// Formatting precision of timestamps.
@@ -132,6 +137,9 @@ impl Default for TimestampPrecision {
 pub struct Formatter {
     buf: Rc<RefCell<Buffer>>,
     write_style: WriteStyle,
+    // Whether the default key-value format escapes values, while it's being called
+    #[cfg_attr(not(feature = "kv"), allow(dead_code))]
+    sanitize: bool,
 }
 
 impl Formatter {
@@ -139,6 +147,7 @@ impl Formatter {
         Formatter {
             buf: Rc::new(RefCell::new(writer.buffer())),
             write_style: writer.write_style(),
+            sanitize: false,
         }
     }
 
@@ -146,13 +155,24 @@ impl Formatter {
         self.write_style
     }
 
-    pub(crate) fn print(&self, writer: &Writer) -> io::Result<()> {
-        writer.print(&self.buf.borrow())
+    pub(crate) fn print(&self, writer: &Writer, meta: &RecordMeta<'_>) -> io::Result<()> {
+        writer.print(&self.buf.borrow(), meta)
+    }
+
+    /// Calls `f` with the formatted bytes.
+    pub(crate) fn with_bytes<R>(&self, f: impl FnOnce(&[u8]) -> R) -> R {
+        f(self.buf.borrow().as_bytes())
     }
 
     pub(crate) fn clear(&mut self) {
         self.buf.borrow_mut().clear();
     }
+
+    /// The number of bytes written so far.
+    #[cfg(feature = "kv")]
+    fn len(&self) -> usize {
+        self.buf.borrow().as_bytes().len()
+    }
 }
 
 #[cfg(feature = "color")]
@@ -218,6 +238,10 @@ pub(crate) type FormatFn = Box<dyn RecordFormat + Sync + Send>;
 pub(crate) struct Builder {
     pub(crate) default_format: ConfigurableFormat,
     pub(crate) custom_format: Option<FormatFn>,
+    /// A pattern format, which takes the message settings of the default format.
+    pub(crate) pattern: Option<PatternFormat>,
+    /// Whether the timestamp of the default format was set, rather than left as the default.
+    pub(crate) timestamp_set: bool,
     built: bool,
 }
 
@@ -225,7 +249,8 @@ impl Builder {
     /// Convert the format into a callable function.
     ///
     /// If the `custom_format` is `Some`, then any `default_format` switches are ignored.
-    /// If the `custom_format` is `None`, then a default format is returned.
+    /// If the `pattern` is `Some`, it takes the message switches of the `default_format`.
+    /// Otherwise, a default format is returned.
     /// Any `default_format` switches set to `false` won't be written by the format.
     pub(crate) fn build(&mut self) -> FormatFn {
         assert!(!self.built, "attempt to re-use consumed builder");
@@ -240,6 +265,10 @@ impl Builder {
 
         if let Some(fmt) = built.custom_format {
             fmt
+        } else if let Some(mut pattern) = built.pattern {
+            pattern.sanitize = built.default_format.sanitize;
+            pattern.max_message_len = built.default_format.max_message_len;
+            Box::new(pattern)
         } else {
             Box::new(built.default_format)
         }
@@ -285,7 +314,10 @@ pub struct ConfigurableFormat {
     pub(crate) source_file: bool,
     pub(crate) source_line_number: bool,
     pub(crate) indent: Option<usize>,
-    pub(crate) suffix: &'static str,
+    pub(crate) suffix: Cow<'static, str>,
+    pub(crate) priority_prefix: bool,
+    pub(crate) sanitize: bool,
+    pub(crate) max_message_len: Option<usize>,
     #[cfg(feature = "kv")]
     pub(crate) kv_format: Option<Box<KvFormatFn>>,
 }
@@ -351,7 +383,42 @@ impl ConfigurableFormat {
 
     /// Configures the end of line suffix.
     pub fn suffix(&mut self, suffix: &'static str) -> &mut Self {
-        self.suffix = suffix;
+        self.suffix = Cow::Borrowed(suffix);
+        self
+    }
+
+    /// Configures an end of line suffix that is only known at runtime.
+    pub(crate) fn owned_suffix(&mut self, suffix: String) -> &mut Self {
+        self.suffix = Cow::Owned(suffix);
+        self
+    }
+
+    /// Whether or not to start each line with the priority of the level, like `<3>`.
+    ///
+    /// systemd reads these `sd-daemon` prefixes to set the priority of each line a service
+    /// writes to the journal. The levels map to priorities as in the
+    /// [`syslog`][crate::syslog] module.
+    pub fn priority_prefix(&mut self, write: bool) -> &mut Self {
+        self.priority_prefix = write;
+        self
+    }
+
+    /// Whether or not to escape control characters in the message, like `\n` or `\x1b`.
+    ///
+    /// This keeps messages with untrusted input from writing what looks like other records,
+    /// or terminal escape sequences. Control characters other than tabs are escaped as in
+    /// [`char::escape_default`], like `\n` and `\u{1b}`, so multiline messages are written
+    /// on a single line. The values written by the default key-value format are escaped too.
+    pub fn sanitize(&mut self, sanitize: bool) -> &mut Self {
+        self.sanitize = sanitize;
+        self
+    }
+
+    /// Configures the number of bytes of the message to write, after which it's cut short
+    /// and marked as `... [truncated]`.
+    /// A value of `None` writes the whole message.
+    pub fn max_message_len(&mut self, max_len: Option<usize>) -> &mut Self {
+        self.max_message_len = max_len;
         self
     }
 
@@ -385,7 +452,10 @@ impl Default for ConfigurableFormat {
             source_file: false,
             source_line_number: false,
             indent: Some(4),
-            suffix: "\n",
+            suffix: Cow::Borrowed("\n"),
+            priority_prefix: false,
+            sanitize: false,
+            max_message_len: None,
             #[cfg(feature = "kv")]
             kv_format: None,
         }
@@ -409,6 +479,7 @@ struct ConfigurableFormatWriter<'a> {
 
 impl ConfigurableFormatWriter<'_> {
     fn write(mut self, record: &Record<'_>) -> io::Result<()> {
+        self.write_priority_prefix(record)?;
         self.write_timestamp()?;
         self.write_level(record)?;
         self.write_module_path(record)?;
@@ -477,6 +548,14 @@ impl ConfigurableFormatWriter<'_> {
         self.write_header_value(format_args!("{level:<5}"))
     }
 
+    fn write_priority_prefix(&mut self, record: &Record<'_>) -> io::Result<()> {
+        if !self.format.priority_prefix {
+            return Ok(());
+        }
+
+        write!(self.buf, "<{}>", crate::syslog::severity(record.level()))
+    }
+
     fn write_timestamp(&mut self) -> io::Result<()> {
         #[cfg(feature = "humantime")]
         {
@@ -553,16 +632,33 @@ impl ConfigurableFormatWriter<'_> {
     }
 
     fn write_args(&mut self, record: &Record<'_>) -> io::Result<()> {
-        match self.format.indent {
+        if self.format.sanitize {
+            self.write_message(record, &Sanitized(record.args()))
+        } else {
+            self.write_message(record, record.args())
+        }
+    }
+
+    fn write_message(&mut self, record: &Record<'_>, message: &dyn Display) -> io::Result<()> {
+        let max_len = self.format.max_message_len;
+        // Every line needs its own priority prefix
+        let line_prefix = if self.format.priority_prefix {
+            Some(crate::syslog::severity(record.level()))
+        } else {
+            None
+        };
+
+        match (self.format.indent, line_prefix) {
             // Fast path for no indentation
-            None => write!(self.buf, "{}", record.args()),
+            (None, None) => write_truncated(&mut *self.buf, message, max_len),
 
-            Some(indent_count) => {
+            (indent_count, line_prefix) => {
                 // Create a wrapper around the buffer only if we have to actually indent the message
 
                 struct IndentWrapper<'a, 'b> {
                     fmt: &'a mut ConfigurableFormatWriter<'b>,
                     indent_count: usize,
+                    line_prefix: Option<u8>,
                 }
 
                 impl Write for IndentWrapper<'_, '_> {
@@ -570,13 +666,11 @@ impl ConfigurableFormatWriter<'_> {
                         let mut first = true;
                         for chunk in buf.split(|&x| x == b'\n') {
                             if !first {
-                                write!(
-                                    self.fmt.buf,
-                                    "{}{:width$}",
-                                    self.fmt.format.suffix,
-                                    "",
-                                    width = self.indent_count
-                                )?;
+                                write!(self.fmt.buf, "{}", self.fmt.format.suffix)?;
+                                if let Some(priority) = self.line_prefix {
+                                    write!(self.fmt.buf, "<{priority}>")?;
+                                }
+                                write!(self.fmt.buf, "{:width$}", "", width = self.indent_count)?;
                             }
                             self.fmt.buf.write_all(chunk)?;
                             first = false;
@@ -592,11 +686,12 @@ impl ConfigurableFormatWriter<'_> {
 
                 // The explicit scope here is just to make older versions of Rust happy
                 {
-                    let mut wrapper = IndentWrapper {
+                    let wrapper = IndentWrapper {
                         fmt: self,
-                        indent_count,
+                        indent_count: indent_count.unwrap_or(0),
+                        line_prefix,
                     };
-                    write!(wrapper, "{}", record.args())?;
+                    write_truncated(wrapper, message, max_len)?;
                 }
 
                 Ok(())
@@ -611,7 +706,22 @@ impl ConfigurableFormatWriter<'_> {
             .kv_format
             .as_deref()
             .unwrap_or(&default_kv_format);
-        format(self.buf, record.key_values())
+
+        let start = self.buf.len();
+        self.buf.sanitize = self.format.sanitize;
+        let result = format(self.buf, record.key_values());
+        self.buf.sanitize = false;
+        result?;
+
+        // Every line needs its own priority prefix, like in the message
+        if self.format.priority_prefix {
+            let prefix = format!("<{}>", crate::syslog::severity(record.level()));
+            self.buf
+                .buf
+                .borrow_mut()
+                .prefix_lines(start, prefix.as_bytes());
+        }
+        Ok(())
     }
 }
 
@@ -671,7 +781,10 @@ mod tests {
                 #[cfg(feature = "kv")]
                 kv_format: Some(Box::new(hidden_kv_format)),
                 indent: None,
-                suffix: "\n",
+                suffix: "\n".into(),
+                priority_prefix: false,
+                sanitize: false,
+                max_message_len: None,
             },
             written_header_value: false,
             buf: &mut f,
@@ -695,7 +808,10 @@ mod tests {
                 #[cfg(feature = "kv")]
                 kv_format: Some(Box::new(hidden_kv_format)),
                 indent: None,
-                suffix: "\n",
+                suffix: "\n".into(),
+                priority_prefix: false,
+                sanitize: false,
+                max_message_len: None,
             },
             written_header_value: false,
             buf: &mut f,
@@ -719,7 +835,10 @@ mod tests {
                 #[cfg(feature = "kv")]
                 kv_format: Some(Box::new(hidden_kv_format)),
                 indent: Some(4),
-                suffix: "\n",
+                suffix: "\n".into(),
+                priority_prefix: false,
+                sanitize: false,
+                max_message_len: None,
             },
             written_header_value: false,
             buf: &mut f,
@@ -743,7 +862,10 @@ mod tests {
                 #[cfg(feature = "kv")]
                 kv_format: Some(Box::new(hidden_kv_format)),
                 indent: Some(0),
-                suffix: "\n",
+                suffix: "\n".into(),
+                priority_prefix: false,
+                sanitize: false,
+                max_message_len: None,
             },
             written_header_value: false,
             buf: &mut f,
@@ -767,7 +889,10 @@ mod tests {
                 #[cfg(feature = "kv")]
                 kv_format: Some(Box::new(hidden_kv_format)),
                 indent: Some(4),
-                suffix: "\n",
+                suffix: "\n".into(),
+                priority_prefix: false,
+                sanitize: false,
+                max_message_len: None,
             },
             written_header_value: false,
             buf: &mut f,
@@ -791,7 +916,10 @@ mod tests {
                 #[cfg(feature = "kv")]
                 kv_format: Some(Box::new(hidden_kv_format)),
                 indent: None,
-                suffix: "\n\n",
+                suffix: "\n\n".into(),
+                priority_prefix: false,
+                sanitize: false,
+                max_message_len: None,
             },
             written_header_value: false,
             buf: &mut f,
@@ -815,7 +943,10 @@ mod tests {
                 #[cfg(feature = "kv")]
                 kv_format: Some(Box::new(hidden_kv_format)),
                 indent: Some(4),
-                suffix: "\n\n",
+                suffix: "\n\n".into(),
+                priority_prefix: false,
+                sanitize: false,
+                max_message_len: None,
             },
             written_header_value: false,
             buf: &mut f,
@@ -824,6 +955,60 @@ mod tests {
         assert_eq!("log\n\n    message\n\n", written);
     }
 
+    #[test]
+    fn format_priority_prefix() {
+        let mut f = formatter();
+
+        let written = write(ConfigurableFormatWriter {
+            format: &ConfigurableFormat {
+                timestamp: None,
+                module_path: false,
+                target: false,
+                level: true,
+                source_file: false,
+                source_line_number: false,
+                #[cfg(feature = "kv")]
+                kv_format: Some(Box::new(hidden_kv_format)),
+                indent: None,
+                suffix: "\n".into(),
+                priority_prefix: true,
+                sanitize: false,
+                max_message_len: None,
+            },
+            written_header_value: false,
+            buf: &mut f,
+        });
+
+        assert_eq!("<6>[INFO ] log\n<6>message\n", written);
+    }
+
+    #[test]
+    fn format_priority_prefix_with_indent() {
+        let mut f = formatter();
+
+        let written = write(ConfigurableFormatWriter {
+            format: &ConfigurableFormat {
+                timestamp: None,
+                module_path: false,
+                target: false,
+                level: false,
+                source_file: false,
+                source_line_number: false,
+                #[cfg(feature = "kv")]
+                kv_format: Some(Box::new(hidden_kv_format)),
+                indent: Some(4),
+                suffix: "\n".into(),
+                priority_prefix: true,
+                sanitize: false,
+                max_message_len: None,
+            },
+            written_header_value: false,
+            buf: &mut f,
+        });
+
+        assert_eq!("<6>log\n<6>    message\n", written);
+    }
+
     #[test]
     fn format_target() {
         let mut f = formatter();
@@ -841,7 +1026,10 @@ mod tests {
                     #[cfg(feature = "kv")]
                     kv_format: Some(Box::new(hidden_kv_format)),
                     indent: None,
-                    suffix: "\n",
+                    suffix: "\n".into(),
+                    priority_prefix: false,
+                    sanitize: false,
+                    max_message_len: None,
                 },
                 written_header_value: false,
                 buf: &mut f,
@@ -866,7 +1054,10 @@ mod tests {
                 #[cfg(feature = "kv")]
                 kv_format: Some(Box::new(hidden_kv_format)),
                 indent: None,
-                suffix: "\n",
+                suffix: "\n".into(),
+                priority_prefix: false,
+                sanitize: false,
+                max_message_len: None,
             },
             written_header_value: false,
             buf: &mut f,
@@ -892,7 +1083,10 @@ mod tests {
                     #[cfg(feature = "kv")]
                     kv_format: Some(Box::new(hidden_kv_format)),
                     indent: None,
-                    suffix: "\n",
+                    suffix: "\n".into(),
+                    priority_prefix: false,
+                    sanitize: false,
+                    max_message_len: None,
                 },
                 written_header_value: false,
                 buf: &mut f,
@@ -917,7 +1111,10 @@ mod tests {
                 #[cfg(feature = "kv")]
                 kv_format: Some(Box::new(hidden_kv_format)),
                 indent: None,
-                suffix: "\n",
+                suffix: "\n".into(),
+                priority_prefix: false,
+                sanitize: false,
+                max_message_len: None,
             },
             written_header_value: false,
             buf: &mut f,
@@ -950,7 +1147,10 @@ mod tests {
                     source_line_number: false,
                     kv_format: Some(Box::new(default_kv_format)),
                     indent: None,
-                    suffix: "\n",
+                    suffix: "\n".into(),
+                    priority_prefix: false,
+                    sanitize: false,
+                    max_message_len: None,
                 },
                 written_header_value: false,
                 buf: &mut f,
@@ -987,7 +1187,10 @@ mod tests {
                     source_line_number: true,
                     kv_format: Some(Box::new(default_kv_format)),
                     indent: None,
-                    suffix: "\n",
+                    suffix: "\n".into(),
+                    priority_prefix: false,
+                    sanitize: false,
+                    max_message_len: None,
                 },
                 written_header_value: false,
                 buf: &mut f,
@@ -999,4 +1202,182 @@ mod tests {
             written
         );
     }
+
+    #[test]
+    fn format_sanitized() {
+        let mut f = formatter();
+        let written = write_record(
+            Record::builder()
+                .args(format_args!("user {}", "bob\n[INFO  fake] \x1b[2Jrecord"))
+                .level(Level::Info)
+                .build(),
+            ConfigurableFormatWriter {
+                format: &ConfigurableFormat {
+                    timestamp: None,
+                    module_path: false,
+                    target: false,
+                    level: true,
+                    source_file: false,
+                    source_line_number: false,
+                    #[cfg(feature = "kv")]
+                    kv_format: Some(Box::new(hidden_kv_format)),
+                    indent: Some(4),
+                    suffix: "\n".into(),
+                    priority_prefix: false,
+                    sanitize: true,
+                    max_message_len: None,
+                },
+                written_header_value: false,
+                buf: &mut f,
+            },
+        );
+
+        assert_eq!(
+            "[INFO ] user bob\\n[INFO  fake] \\u{1b}[2Jrecord\n",
+            written
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "color")]
+    fn format_sanitized_keeps_styles() {
+        let writer = crate::writer::Builder::new()
+            .write_style(WriteStyle::Always)
+            .build();
+        let mut f = Formatter::new(&writer);
+        let written = write_record(
+            Record::builder()
+                .args(format_args!("{}", "\x1b[31mred"))
+                .level(Level::Info)
+                .build(),
+            ConfigurableFormatWriter {
+                format: &ConfigurableFormat {
+                    timestamp: None,
+                    module_path: false,
+                    target: false,
+                    level: true,
+                    source_file: false,
+                    source_line_number: false,
+                    #[cfg(feature = "kv")]
+                    kv_format: Some(Box::new(hidden_kv_format)),
+                    indent: None,
+                    suffix: "\n".into(),
+                    priority_prefix: false,
+                    sanitize: true,
+                    max_message_len: None,
+                },
+                written_header_value: false,
+                buf: &mut f,
+            },
+        );
+
+        let level_style = f.default_level_style(Level::Info);
+        assert!(
+            written.contains(&format!("{level_style}INFO {level_style:#}")),
+            "{written:?}"
+        );
+        assert!(written.ends_with(" \\u{1b}[31mred\n"), "{written:?}");
+    }
+
+    #[test]
+    fn format_truncated() {
+        let mut f = formatter();
+
+        let written = write(ConfigurableFormatWriter {
+            format: &ConfigurableFormat {
+                timestamp: None,
+                module_path: false,
+                target: false,
+                level: true,
+                source_file: false,
+                source_line_number: false,
+                #[cfg(feature = "kv")]
+                kv_format: Some(Box::new(hidden_kv_format)),
+                indent: Some(4),
+                suffix: "\n".into(),
+                priority_prefix: false,
+                sanitize: false,
+                max_message_len: Some(6),
+            },
+            written_header_value: false,
+            buf: &mut f,
+        });
+
+        assert_eq!("[INFO ] log\n    me... [truncated]\n", written);
+    }
+
+    #[cfg(feature = "kv")]
+    #[test]
+    fn format_kv_sanitized() {
+        let kvs = &[("user", "bob\nalice")][..];
+        let mut f = formatter();
+        let record = Record::builder()
+            .args(format_args!("login"))
+            .level(Level::Info)
+            .key_values(&kvs)
+            .build();
+
+        let written = write_record(
+            record,
+            ConfigurableFormatWriter {
+                format: &ConfigurableFormat {
+                    timestamp: None,
+                    module_path: false,
+                    target: false,
+                    level: true,
+                    source_file: false,
+                    source_line_number: false,
+                    kv_format: Some(Box::new(default_kv_format)),
+                    indent: None,
+                    suffix: "\n".into(),
+                    priority_prefix: false,
+                    sanitize: true,
+                    max_message_len: None,
+                },
+                written_header_value: false,
+                buf: &mut f,
+            },
+        );
+
+        assert_eq!("[INFO ] login user=bob\\nalice\n", written);
+    }
+
+    #[cfg(feature = "kv")]
+    #[test]
+    fn format_kv_priority_prefix() {
+        let kvs = &[("query", "SELECT *\nFROM users")][..];
+        let mut f = formatter();
+        let record = Record::builder()
+            .args(format_args!("slow\nquery"))
+            .level(Level::Warn)
+            .key_values(&kvs)
+            .build();
+
+        let written = write_record(
+            record,
+            ConfigurableFormatWriter {
+                format: &ConfigurableFormat {
+                    timestamp: None,
+                    module_path: false,
+                    target: false,
+                    level: true,
+                    source_file: false,
+                    source_line_number: false,
+                    kv_format: Some(Box::new(default_kv_format)),
+                    indent: None,
+                    suffix: "\n".into(),
+                    priority_prefix: true,
+                    sanitize: false,
+                    max_message_len: None,
+                },
+                written_header_value: false,
+                buf: &mut f,
+            },
+        );
+
+        assert_eq!(
+            "<4>[WARN ] slow\n<4>query query=SELECT *\n<4>FROM users\n",
+            written
+        );
+    }
 }
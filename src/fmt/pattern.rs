@@ -0,0 +1,540 @@
+use std::fmt::{self, Display};
+use std::io::{self, Write as _};
+use std::str::FromStr;
+use std::{error, mem, thread};
+
+use log::Record;
+
+use super::sanitize::{write_truncated, Sanitized};
+#[cfg(feature = "color")]
+use super::StyledValue;
+use super::{Formatter, RecordFormat, TimestampPrecision};
+
+/// A format given by a pattern string, like `"{ts:millis} {level:<5} {target} - {msg}"`.
+///
+/// The pattern is parsed once, so formatting a record only writes out its parts. This lets
+/// the format be changed without recompiling, like from the `RUST_LOG_FORMAT` environment
+/// variable read by [`Builder::parse_env`][crate::Builder::parse_env].
+///
+/// # Placeholders
+///
+/// - `{ts}`: the timestamp, in seconds. `{ts:secs}`, `{ts:millis}`, `{ts:micros}` and
+///   `{ts:nanos}` set the precision. Requires the `humantime` feature.
+/// - `{level}`: the level, styled like in the default format.
+/// - `{target}`: the target.
+/// - `{module}`: the module path, if known.
+/// - `{file}`: the source file, if known.
+/// - `{line}`: the source line, if known.
+/// - `{msg}`: the message.
+/// - `{kv}`: the key-values, as `key=value` pairs separated by spaces. Only written with
+///   the `kv` feature.
+/// - `{thread}`: the name of the current thread, or `<unnamed>`.
+///
+/// Placeholders other than `{ts}` and `{kv}` can be padded to a width, aligned to the left
+/// with `{level:<5}`, the right with `{line:>4}` or the center with `{target:^20}`. A width
+/// on its own like `{level:5}` aligns to the left. Unknown fields are written empty.
+///
+/// `{{` and `}}` write a literal brace. Each record ends with a newline.
+///
+/// When set with [`Builder::format_pattern`][crate::Builder::format_pattern], `{msg}` and
+/// `{kv}` follow [`Builder::format_sanitize`][crate::Builder::format_sanitize], and `{msg}`
+/// follows [`Builder::format_max_message_len`][crate::Builder::format_max_message_len],
+/// like in the default format. The message is cut short before it's padded.
+///
+/// # Examples
+///
+/// ```
+/// use env_logger::fmt::PatternFormat;
+///
+/// let format: PatternFormat = "[{level:<5} {target}] {msg}".parse().unwrap();
+///
+/// env_logger::Builder::new().format_pattern(format).init();
+/// ```
+#[derive(Clone, Debug)]
+pub struct PatternFormat {
+    segments: Vec<Segment>,
+    pub(crate) sanitize: bool,
+    pub(crate) max_message_len: Option<usize>,
+}
+
+#[derive(Clone, Debug)]
+enum Segment {
+    Literal(String),
+    #[cfg_attr(not(feature = "humantime"), allow(dead_code))]
+    Timestamp(TimestampPrecision),
+    Field(Field, Option<Alignment>),
+    KeyValues,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Field {
+    Level,
+    Target,
+    Module,
+    File,
+    Line,
+    Message,
+    Thread,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Alignment {
+    align: Align,
+    width: usize,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Align {
+    Left,
+    Right,
+    Center,
+}
+
+impl PatternFormat {
+    /// Parses a pattern.
+    ///
+    /// See the [placeholders](#placeholders) it can contain.
+    ///
+    /// # Errors
+    ///
+    /// Fails if a brace isn't matched, or a placeholder or its options aren't known.
+    pub fn parse(pattern: &str) -> Result<Self, PatternError> {
+        let mut segments = Vec::new();
+        let mut literal = String::new();
+
+        let mut rest = pattern;
+        while let Some(i) = rest.find(['{', '}']) {
+            literal.push_str(&rest[..i]);
+            let brace = &rest[i..=i];
+            rest = &rest[i + 1..];
+
+            if let Some(escaped) = rest.strip_prefix(brace) {
+                literal.push_str(brace);
+                rest = escaped;
+                continue;
+            }
+            if brace == "}" {
+                return Err(PatternError::new("unmatched `}`"));
+            }
+
+            let Some(end) = rest.find('}') else {
+                return Err(PatternError::new("unclosed `{`"));
+            };
+            if !literal.is_empty() {
+                segments.push(Segment::Literal(mem::take(&mut literal)));
+            }
+            segments.push(Segment::parse(&rest[..end])?);
+            rest = &rest[end + 1..];
+        }
+        literal.push_str(rest);
+        if !literal.is_empty() {
+            segments.push(Segment::Literal(literal));
+        }
+
+        Ok(Self {
+            segments,
+            sanitize: false,
+            max_message_len: None,
+        })
+    }
+
+    /// Format the [`Record`] as given by the pattern.
+    pub fn format(&self, formatter: &mut Formatter, record: &Record<'_>) -> io::Result<()> {
+        for segment in &self.segments {
+            match segment {
+                Segment::Literal(literal) => formatter.write_all(literal.as_bytes())?,
+                Segment::Timestamp(precision) => write_timestamp(formatter, *precision)?,
+                Segment::Field(field, alignment) => {
+                    self.write_field(formatter, record, *field, *alignment)?;
+                }
+                Segment::KeyValues => write_kv(formatter, record, self.sanitize)?,
+            }
+        }
+        writeln!(formatter)
+    }
+}
+
+impl Segment {
+    fn parse(placeholder: &str) -> Result<Self, PatternError> {
+        let (name, spec) = match placeholder.split_once(':') {
+            Some((name, spec)) => (name, Some(spec)),
+            None => (placeholder, None),
+        };
+
+        let field = match name {
+            "ts" => return parse_timestamp(spec),
+            "kv" if spec.is_none() => return Ok(Self::KeyValues),
+            "level" => Field::Level,
+            "target" => Field::Target,
+            "module" => Field::Module,
+            "file" => Field::File,
+            "line" => Field::Line,
+            "msg" => Field::Message,
+            "thread" => Field::Thread,
+            _ => {
+                return Err(PatternError::new(format!(
+                    "unknown placeholder `{{{placeholder}}}`"
+                )))
+            }
+        };
+        let alignment = spec
+            .map(|spec| {
+                Alignment::parse(spec).ok_or_else(|| {
+                    PatternError::new(format!("invalid width in `{{{placeholder}}}`"))
+                })
+            })
+            .transpose()?;
+        Ok(Self::Field(field, alignment))
+    }
+}
+
+#[cfg(feature = "humantime")]
+fn parse_timestamp(spec: Option<&str>) -> Result<Segment, PatternError> {
+    let precision = match spec {
+        None | Some("secs") => TimestampPrecision::Seconds,
+        Some("millis") => TimestampPrecision::Millis,
+        Some("micros") => TimestampPrecision::Micros,
+        Some("nanos") => TimestampPrecision::Nanos,
+        Some(spec) => {
+            return Err(PatternError::new(format!(
+                "unknown timestamp precision `{spec}`"
+            )))
+        }
+    };
+    Ok(Segment::Timestamp(precision))
+}
+
+#[cfg(not(feature = "humantime"))]
+fn parse_timestamp(_: Option<&str>) -> Result<Segment, PatternError> {
+    Err(PatternError::new(
+        "`{ts}` needs the `humantime` feature of env_logger",
+    ))
+}
+
+impl Alignment {
+    fn parse(spec: &str) -> Option<Self> {
+        let (align, width) = match spec.as_bytes().first()? {
+            b'<' => (Align::Left, &spec[1..]),
+            b'>' => (Align::Right, &spec[1..]),
+            b'^' => (Align::Center, &spec[1..]),
+            _ => (Align::Left, spec),
+        };
+        Some(Self {
+            align,
+            width: width.parse().ok()?,
+        })
+    }
+}
+
+#[cfg(feature = "humantime")]
+fn write_timestamp(formatter: &mut Formatter, precision: TimestampPrecision) -> io::Result<()> {
+    let ts = match precision {
+        TimestampPrecision::Seconds => formatter.timestamp_seconds(),
+        TimestampPrecision::Millis => formatter.timestamp_millis(),
+        TimestampPrecision::Micros => formatter.timestamp_micros(),
+        TimestampPrecision::Nanos => formatter.timestamp_nanos(),
+    };
+    write!(formatter, "{ts}")
+}
+
+#[cfg(not(feature = "humantime"))]
+fn write_timestamp(_: &mut Formatter, _: TimestampPrecision) -> io::Result<()> {
+    Ok(())
+}
+
+impl PatternFormat {
+    fn write_field(
+        &self,
+        formatter: &mut Formatter,
+        record: &Record<'_>,
+        field: Field,
+        alignment: Option<Alignment>,
+    ) -> io::Result<()> {
+        match field {
+            Field::Level => {
+                let level = record.level();
+                #[cfg(feature = "color")]
+                let level = StyledValue {
+                    style: formatter.default_level_style(level),
+                    value: level,
+                };
+                write_aligned(formatter, level, alignment)
+            }
+            Field::Target => write_aligned(formatter, record.target(), alignment),
+            Field::Module => {
+                write_aligned(formatter, record.module_path().unwrap_or(""), alignment)
+            }
+            Field::File => write_aligned(formatter, record.file().unwrap_or(""), alignment),
+            Field::Line => match record.line() {
+                Some(line) => write_aligned(formatter, line, alignment),
+                None => write_aligned(formatter, "", alignment),
+            },
+            Field::Message => self.write_message(formatter, record, alignment),
+            Field::Thread => write_aligned(
+                formatter,
+                thread::current().name().unwrap_or("<unnamed>"),
+                alignment,
+            ),
+        }
+    }
+
+    fn write_message(
+        &self,
+        formatter: &mut Formatter,
+        record: &Record<'_>,
+        alignment: Option<Alignment>,
+    ) -> io::Result<()> {
+        let sanitized;
+        let message: &dyn Display = if self.sanitize {
+            sanitized = Sanitized(record.args());
+            &sanitized
+        } else {
+            record.args()
+        };
+
+        if alignment.is_none() {
+            return write_truncated(formatter, message, self.max_message_len);
+        }
+        // Pad the message once it's cut short, so the padding isn't cut instead
+        let mut truncated = Vec::new();
+        write_truncated(&mut truncated, message, self.max_message_len)?;
+        write_aligned(formatter, String::from_utf8_lossy(&truncated), alignment)
+    }
+}
+
+fn write_aligned(
+    formatter: &mut Formatter,
+    value: impl Display,
+    alignment: Option<Alignment>,
+) -> io::Result<()> {
+    match alignment {
+        None => write!(formatter, "{value}"),
+        Some(Alignment {
+            align: Align::Left,
+            width,
+        }) => write!(formatter, "{value:<width$}"),
+        Some(Alignment {
+            align: Align::Right,
+            width,
+        }) => write!(formatter, "{value:>width$}"),
+        Some(Alignment {
+            align: Align::Center,
+            width,
+        }) => write!(formatter, "{value:^width$}"),
+    }
+}
+
+#[cfg(feature = "kv")]
+fn write_kv(formatter: &mut Formatter, record: &Record<'_>, sanitize: bool) -> io::Result<()> {
+    use log::kv::{Error, Key, Value, VisitSource};
+
+    struct Pairs<'a> {
+        formatter: &'a mut Formatter,
+        sanitize: bool,
+        first: bool,
+    }
+
+    impl<'kvs> VisitSource<'kvs> for Pairs<'_> {
+        fn visit_pair(&mut self, key: Key<'kvs>, value: Value<'kvs>) -> Result<(), Error> {
+            if !mem::take(&mut self.first) {
+                write!(self.formatter, " ")?;
+            }
+            if self.sanitize {
+                write!(self.formatter, "{key}={}", Sanitized(value))?;
+            } else {
+                write!(self.formatter, "{key}={value}")?;
+            }
+            Ok(())
+        }
+    }
+
+    record
+        .key_values()
+        .visit(&mut Pairs {
+            formatter,
+            sanitize,
+            first: true,
+        })
+        .map_err(|e| io::Error::new(io::ErrorKind::Other, e))
+}
+
+#[cfg(not(feature = "kv"))]
+fn write_kv(_: &mut Formatter, _: &Record<'_>, _: bool) -> io::Result<()> {
+    Ok(())
+}
+
+impl FromStr for PatternFormat {
+    type Err = PatternError;
+
+    fn from_str(pattern: &str) -> Result<Self, Self::Err> {
+        Self::parse(pattern)
+    }
+}
+
+impl RecordFormat for PatternFormat {
+    fn format(&self, formatter: &mut Formatter, record: &Record<'_>) -> io::Result<()> {
+        self.format(formatter, record)
+    }
+}
+
+/// An error parsing a [`PatternFormat`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PatternError {
+    message: String,
+}
+
+impl PatternError {
+    fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+        }
+    }
+}
+
+impl Display for PatternError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid format pattern: {}", self.message)
+    }
+}
+
+impl error::Error for PatternError {}
+
+#[cfg(test)]
+mod tests {
+    use log::{Level, Record};
+
+    use super::PatternFormat;
+    use crate::fmt::{Formatter, WriteStyle};
+
+    fn format(pattern: &str, record: &Record<'_>) -> String {
+        write(&pattern.parse().unwrap(), record)
+    }
+
+    fn write(pattern: &PatternFormat, record: &Record<'_>) -> String {
+        let writer = crate::writer::Builder::new()
+            .write_style(WriteStyle::Never)
+            .build();
+        let mut formatter = Formatter::new(&writer);
+
+        pattern.format(&mut formatter, record).unwrap();
+        formatter.with_bytes(|bytes| String::from_utf8(bytes.to_vec()).unwrap())
+    }
+
+    #[test]
+    fn format_fields() {
+        let written = format(
+            "{{{level:<5}}} {target:>8}|{module}|{file}:{line:^5}|{msg}",
+            &Record::builder()
+                .args(format_args!("log\nmessage"))
+                .level(Level::Warn)
+                .target("app")
+                .module_path(Some("app::db"))
+                .file(Some("src/db.rs"))
+                .line(Some(7))
+                .build(),
+        );
+
+        assert_eq!(
+            written,
+            "{WARN }      app|app::db|src/db.rs:  7  |log\nmessage\n"
+        );
+    }
+
+    #[test]
+    fn format_unknown_fields_empty() {
+        let written = format(
+            "[{module}|{file}|{line:3}] {msg}",
+            &Record::builder().args(format_args!("message")).build(),
+        );
+
+        assert_eq!(written, "[||   ] message\n");
+    }
+
+    #[test]
+    fn format_thread() {
+        let written = std::thread::Builder::new()
+            .name("worker".to_owned())
+            .spawn(|| format("{thread}", &Record::builder().build()))
+            .unwrap()
+            .join()
+            .unwrap();
+
+        assert_eq!(written, "worker\n");
+    }
+
+    #[test]
+    #[cfg(feature = "kv")]
+    fn format_kv() {
+        let kvs = [("a", 1), ("b", 2)];
+        let written = format(
+            "{msg} {kv}",
+            &Record::builder()
+                .args(format_args!("message"))
+                .key_values(&kvs)
+                .build(),
+        );
+
+        assert_eq!(written, "message a=1 b=2\n");
+    }
+
+    #[test]
+    fn format_sanitized_truncated_message() {
+        let mut pattern = PatternFormat::parse("{msg}|{msg:<12}|").unwrap();
+        pattern.sanitize = true;
+        pattern.max_message_len = Some(8);
+
+        let written = write(
+            &pattern,
+            &Record::builder()
+                .args(format_args!("a\nb\x1b[2J and more"))
+                .build(),
+        );
+
+        assert_eq!(
+            written,
+            "a\\nb\\u{1... [truncated]|a\\nb\\u{1... [truncated]|\n"
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "kv")]
+    fn format_kv_sanitized() {
+        let mut pattern = PatternFormat::parse("{kv}").unwrap();
+        pattern.sanitize = true;
+
+        let kvs = [("user", "evil\n[ERROR] fake")];
+        let written = write(&pattern, &Record::builder().key_values(&kvs).build());
+
+        assert_eq!(written, "user=evil\\n[ERROR] fake\n");
+    }
+
+    #[test]
+    #[cfg(feature = "humantime")]
+    fn format_timestamp() {
+        let written = format("{ts:millis}", &Record::builder().build());
+
+        // Like `2024-01-01T00:00:00.000Z`
+        assert_eq!(written.len(), 25, "{written}");
+        assert!(written.ends_with("Z\n"), "{written}");
+    }
+
+    #[test]
+    fn parse_errors() {
+        let error = |pattern: &str| PatternFormat::parse(pattern).unwrap_err().to_string();
+
+        assert_eq!(error("{msg"), "invalid format pattern: unclosed `{`");
+        assert_eq!(error("msg}"), "invalid format pattern: unmatched `}`");
+        assert_eq!(
+            error("{message}"),
+            "invalid format pattern: unknown placeholder `{message}`"
+        );
+        assert_eq!(
+            error("{level:<x}"),
+            "invalid format pattern: invalid width in `{level:<x}`"
+        );
+        assert_eq!(
+            error("{kv:5}"),
+            "invalid format pattern: unknown placeholder `{kv:5}`"
+        );
+    }
+}
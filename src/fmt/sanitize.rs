@@ -0,0 +1,113 @@
+use std::fmt::{self, Display, Write as _};
+use std::io::{self, Write};
+
+/// Written after a message that was cut short.
+const TRUNCATED: &str = "... [truncated]";
+
+/// A value formatted with its control characters escaped, except tabs.
+///
+/// This keeps a value from starting new lines or writing terminal escape sequences, like
+/// `\n` or `\u{1b}[2J`.
+pub(crate) struct Sanitized<T>(pub(crate) T);
+
+impl<T: Display> Display for Sanitized<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(Escape(f), "{}", self.0)
+    }
+}
+
+struct Escape<W>(W);
+
+impl<W: fmt::Write> fmt::Write for Escape<W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let mut start = 0;
+        for (i, c) in s.char_indices() {
+            if c.is_control() && c != '\t' {
+                self.0.write_str(&s[start..i])?;
+                write!(self.0, "{}", c.escape_default())?;
+                start = i + c.len_utf8();
+            }
+        }
+        self.0.write_str(&s[start..])
+    }
+}
+
+/// Writes `message` to `out`, cutting it short after `max_len` bytes if set.
+pub(crate) fn write_truncated(
+    mut out: impl Write,
+    message: &dyn Display,
+    max_len: Option<usize>,
+) -> io::Result<()> {
+    let Some(max_len) = max_len else {
+        return write!(out, "{message}");
+    };
+
+    let mut out = Truncate {
+        inner: out,
+        remaining: max_len,
+        truncated: false,
+    };
+    write!(out, "{message}")?;
+    if out.truncated {
+        out.inner.write_all(TRUNCATED.as_bytes())?;
+    }
+    Ok(())
+}
+
+/// Drops whatever is written past a number of bytes.
+struct Truncate<W> {
+    inner: W,
+    remaining: usize,
+    truncated: bool,
+}
+
+impl<W: Write> Write for Truncate<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if buf.len() <= self.remaining {
+            self.inner.write_all(buf)?;
+            self.remaining -= buf.len();
+        } else if !self.truncated {
+            // Formatting writes whole strings, so cut at a character boundary
+            let mut end = self.remaining;
+            while end > 0 && buf[end] & 0xC0 == 0x80 {
+                end -= 1;
+            }
+            self.inner.write_all(&buf[..end])?;
+            self.remaining = 0;
+            self.truncated = true;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{write_truncated, Sanitized};
+
+    fn truncated(message: &str, max_len: usize) -> String {
+        let mut out = Vec::new();
+        write_truncated(&mut out, &message, Some(max_len)).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn escape_control_characters() {
+        assert_eq!(
+            Sanitized("user\n[ERROR] fake\r\x1b[2J\x07\x7f\u{9b}ünï\tcode").to_string(),
+            "user\\n[ERROR] fake\\r\\u{1b}[2J\\u{7}\\u{7f}\\u{9b}ünï\tcode"
+        );
+    }
+
+    #[test]
+    fn truncate_messages() {
+        assert_eq!(truncated("short", 5), "short");
+        assert_eq!(truncated("longer", 5), "longe... [truncated]");
+        assert_eq!(truncated("ünï", 2), "ü... [truncated]");
+        assert_eq!(truncated("ünï", 3), "ün... [truncated]");
+        assert_eq!(truncated("ünï", 1), "... [truncated]");
+    }
+}
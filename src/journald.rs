@@ -0,0 +1,368 @@
+//! Logging to the systemd journal.
+//!
+//! [`Journald`] formats records in the [native journal protocol], and a [`JournaldSocket`]
+//! sends them to `systemd-journald`. [`Builder::journald`] sets both up at once. Unlike
+//! writing to stderr under systemd, this keeps the metadata of each record as separate
+//! fields that can be queried with `journalctl`:
+//!
+//! | Field                 | Value                                          |
+//! |-----------------------|------------------------------------------------|
+//! | `MESSAGE`             | The message                                    |
+//! | `PRIORITY`            | The syslog severity of the level, from 3 to 7  |
+//! | `CODE_FILE`           | The source file, if known                      |
+//! | `CODE_LINE`           | The source line, if known                      |
+//! | `CODE_MODULE`         | The module path, if known                      |
+//! | `TARGET`              | The target                                     |
+//! | `SYSLOG_IDENTIFIER`   | The file name of the current executable        |
+//!
+//! With the `kv` feature, each key-value of a record is sent as a field too. Keys are
+//! uppercased, with characters other than ASCII letters, digits and `_` replaced by `_`,
+//! since those are the only ones journald accepts.
+//!
+//! Each record is sent as a single datagram, so records too large for one fail to send.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use env_logger::journald::{Journald, JournaldSocket};
+//!
+//! env_logger::Builder::from_default_env()
+//!     .journald(Journald::new(), JournaldSocket::new()?)
+//!     .init();
+//! # Ok::<(), std::io::Error>(())
+//! ```
+//!
+//! [native journal protocol]: https://systemd.io/JOURNAL_NATIVE_PROTOCOL/
+//! [`Builder::journald`]: crate::Builder::journald
+
+use std::io;
+use std::os::unix::net::UnixDatagram;
+use std::path::Path;
+
+use log::Record;
+
+use crate::fmt::Formatter;
+use crate::syslog::severity;
+
+/// The path of the socket `systemd-journald` listens on for native messages.
+pub const JOURNAL_SOCKET: &str = "/run/systemd/journal/socket";
+
+/// Formats records as native journal entries.
+///
+/// See the [module documentation][self] for more details.
+#[derive(Clone, Debug)]
+pub struct Journald {
+    syslog_identifier: Option<String>,
+    fields: Vec<(String, String)>,
+}
+
+impl Journald {
+    /// Creates the format with defaults.
+    pub fn new() -> Self {
+        Self {
+            syslog_identifier: std::env::current_exe()
+                .ok()
+                .and_then(|exe| Some(exe.file_name()?.to_string_lossy().into_owned())),
+            fields: Vec::new(),
+        }
+    }
+
+    /// Sets the `SYSLOG_IDENTIFIER` of the entries.
+    pub fn syslog_identifier(&mut self, identifier: impl Into<String>) -> &mut Self {
+        self.syslog_identifier = Some(identifier.into());
+        self
+    }
+
+    /// Adds a field to every entry.
+    ///
+    /// The name is sanitized the same way as the keys of key-values.
+    pub fn field(&mut self, name: &str, value: impl Into<String>) -> &mut Self {
+        if let Some(name) = field_name(name) {
+            self.fields.push((name, value.into()));
+        }
+        self
+    }
+
+    /// Formats `record` as a native journal entry.
+    ///
+    /// The entry is binary, so this is only useful to send it to journald.
+    pub fn format(&self, buf: &mut Formatter, record: &Record<'_>) -> io::Result<()> {
+        self.write(buf, record)
+    }
+
+    fn write(&self, buf: &mut impl io::Write, record: &Record<'_>) -> io::Result<()> {
+        write_field(buf, "MESSAGE", &record.args().to_string())?;
+        write_field(buf, "PRIORITY", &severity(record.level()).to_string())?;
+        if let Some(file) = record.file() {
+            write_field(buf, "CODE_FILE", file)?;
+        }
+        if let Some(line) = record.line() {
+            write_field(buf, "CODE_LINE", &line.to_string())?;
+        }
+        if let Some(module_path) = record.module_path() {
+            write_field(buf, "CODE_MODULE", module_path)?;
+        }
+        write_field(buf, "TARGET", record.target())?;
+        if let Some(identifier) = &self.syslog_identifier {
+            write_field(buf, "SYSLOG_IDENTIFIER", identifier)?;
+        }
+        for (name, value) in &self.fields {
+            write_field(buf, name, value)?;
+        }
+        self.write_key_values(buf, record)
+    }
+
+    #[cfg(not(feature = "kv"))]
+    fn write_key_values(&self, _: &mut impl io::Write, _: &Record<'_>) -> io::Result<()> {
+        Ok(())
+    }
+
+    #[cfg(feature = "kv")]
+    fn write_key_values(&self, buf: &mut impl io::Write, record: &Record<'_>) -> io::Result<()> {
+        use log::kv::{Error, Key, Value, VisitSource};
+
+        struct Fields<W>(W);
+
+        impl<'kvs, W: io::Write> VisitSource<'kvs> for Fields<W> {
+            fn visit_pair(&mut self, key: Key<'_>, value: Value<'kvs>) -> Result<(), Error> {
+                if let Some(name) = field_name(key.as_str()) {
+                    write_field(&mut self.0, &name, &value.to_string())?;
+                }
+                Ok(())
+            }
+        }
+
+        record
+            .key_values()
+            .visit(&mut Fields(buf))
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, e))
+    }
+}
+
+impl Default for Journald {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Writes a field, using the binary form if the value spans lines.
+fn write_field(buf: &mut impl io::Write, name: &str, value: &str) -> io::Result<()> {
+    if value.contains('\n') {
+        buf.write_all(name.as_bytes())?;
+        buf.write_all(b"\n")?;
+        buf.write_all(&(value.len() as u64).to_le_bytes())?;
+        buf.write_all(value.as_bytes())?;
+        buf.write_all(b"\n")
+    } else {
+        writeln!(buf, "{name}={value}")
+    }
+}
+
+/// The name of a field: at most 64 uppercase ASCII letters, digits and `_`, starting with
+/// a letter, or `None` if there's nothing left of `name`.
+fn field_name(name: &str) -> Option<String> {
+    let name: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        // Names starting with `_` are reserved for fields set by journald
+        .skip_while(|c| !c.is_ascii_alphabetic())
+        .take(64)
+        .collect();
+    (!name.is_empty()).then_some(name)
+}
+
+/// A datagram socket to `systemd-journald`.
+///
+/// Each write is sent as a single entry.
+#[derive(Debug)]
+pub struct JournaldSocket {
+    socket: UnixDatagram,
+}
+
+impl JournaldSocket {
+    /// Connects to journald at [`JOURNAL_SOCKET`].
+    pub fn new() -> io::Result<Self> {
+        Self::connect(JOURNAL_SOCKET)
+    }
+
+    /// Connects to journald, or something speaking its protocol, at `path`.
+    pub fn connect(path: impl AsRef<Path>) -> io::Result<Self> {
+        let socket = UnixDatagram::unbound()?;
+        socket.connect(path)?;
+        Ok(Self { socket })
+    }
+}
+
+impl io::Write for JournaldSocket {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.socket.send(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use log::{Level, Record};
+
+    use super::{field_name, Journald};
+
+    fn journald() -> Journald {
+        let mut journald = Journald::new();
+        journald.syslog_identifier("myapp");
+        journald
+    }
+
+    fn write(journald: &Journald, record: &Record<'_>) -> Vec<u8> {
+        let mut buf = Vec::new();
+        journald.write(&mut buf, record).unwrap();
+        buf
+    }
+
+    /// Parses an entry into its fields.
+    fn parse(mut entry: &[u8]) -> Vec<(String, String)> {
+        let mut fields = Vec::new();
+        while !entry.is_empty() {
+            let end = entry.iter().position(|b| *b == b'\n').unwrap();
+            let line = std::str::from_utf8(&entry[..end]).unwrap();
+            entry = &entry[end + 1..];
+            let (name, value) = match line.split_once('=') {
+                Some((name, value)) => (name, value.as_bytes()),
+                None => {
+                    let len = u64::from_le_bytes(entry[..8].try_into().unwrap()) as usize;
+                    let value = &entry[8..8 + len];
+                    assert_eq!(entry[8 + len], b'\n');
+                    entry = &entry[9 + len..];
+                    (line, value)
+                }
+            };
+            fields.push((name.to_owned(), String::from_utf8(value.to_vec()).unwrap()));
+        }
+        fields
+    }
+
+    #[test]
+    fn format_entry() {
+        let record = Record::builder()
+            .level(Level::Warn)
+            .target("myapp::db")
+            .module_path(Some("myapp::db::pool"))
+            .file(Some("src/db/pool.rs"))
+            .line(Some(42))
+            .args(format_args!("slow query"))
+            .build();
+
+        assert_eq!(
+            String::from_utf8(write(&journald(), &record)).unwrap(),
+            "MESSAGE=slow query\n\
+             PRIORITY=4\n\
+             CODE_FILE=src/db/pool.rs\n\
+             CODE_LINE=42\n\
+             CODE_MODULE=myapp::db::pool\n\
+             TARGET=myapp::db\n\
+             SYSLOG_IDENTIFIER=myapp\n"
+        );
+    }
+
+    #[test]
+    fn format_multiline_values() {
+        let mut journald = journald();
+        journald.field("build info", "a\nb");
+        let record = Record::builder()
+            .level(Level::Trace)
+            .args(format_args!("first\nsecond"))
+            .build();
+
+        let entry = write(&journald, &record);
+        assert_eq!(&entry[..8], b"MESSAGE\n");
+        assert_eq!(&entry[8..16], &12u64.to_le_bytes());
+        assert_eq!(
+            parse(&entry),
+            [
+                ("MESSAGE", "first\nsecond"),
+                ("PRIORITY", "7"),
+                ("TARGET", ""),
+                ("SYSLOG_IDENTIFIER", "myapp"),
+                ("BUILD_INFO", "a\nb"),
+            ]
+            .map(|(name, value)| (name.to_owned(), value.to_owned()))
+        );
+    }
+
+    #[test]
+    fn sanitize_field_names() {
+        assert_eq!(field_name("user.id").as_deref(), Some("USER_ID"));
+        assert_eq!(field_name("_1st-try").as_deref(), Some("ST_TRY"));
+        assert_eq!(field_name("__").as_deref(), None);
+        assert_eq!(field_name(&"a".repeat(100)).unwrap().len(), 64);
+    }
+
+    #[test]
+    #[cfg(feature = "kv")]
+    fn format_key_values() {
+        let kvs: &[(&str, &str)] = &[("user.id", "7"), ("query", "select\n1")];
+        let record = Record::builder()
+            .level(Level::Info)
+            .target("db")
+            .key_values(&kvs)
+            .args(format_args!("query"))
+            .build();
+
+        let fields = parse(&write(&journald(), &record));
+        assert_eq!(
+            fields[fields.len() - 2..],
+            [
+                ("USER_ID".to_owned(), "7".to_owned()),
+                ("QUERY".to_owned(), "select\n1".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn log_to_socket() {
+        use std::os::unix::net::UnixDatagram;
+
+        use log::Log as _;
+
+        use super::JournaldSocket;
+        use crate::{Builder, Framing};
+
+        let path = std::env::temp_dir().join(format!("env_logger-journald-{}", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let server = UnixDatagram::bind(&path).unwrap();
+
+        let logger = Builder::new()
+            .parse_filters("info")
+            // Ignored, since each entry is a datagram of its own
+            .framing(Framing::LengthPrefixed)
+            .journald(journald(), JournaldSocket::connect(&path).unwrap())
+            .build();
+        // 27 bytes, so the length of the value includes an escape character
+        let message = "\x1b[1mbold\x1b[0m\nand then some!";
+        for (level, message) in [(Level::Debug, "hidden"), (Level::Error, message)] {
+            logger.log(
+                &Record::builder()
+                    .level(level)
+                    .target("myapp")
+                    .args(format_args!("{message}"))
+                    .build(),
+            );
+        }
+
+        let mut buf = [0; 256];
+        let len = server.recv(&mut buf).unwrap();
+        let fields = parse(&buf[..len]);
+        assert_eq!(fields[0], ("MESSAGE".to_owned(), message.to_owned()));
+        assert_eq!(fields[1], ("PRIORITY".to_owned(), "3".to_owned()));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
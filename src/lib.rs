@@ -261,6 +261,18 @@
 //!
 //! See the [`fmt`] module for more details about custom formats.
 //!
+//! ### Using a pattern format
+//!
+//! A format can also be given as a pattern string, which is parsed at runtime. It's read
+//! from the `RUST_LOG_FORMAT` environment variable, so the layout can be changed without
+//! rebuilding:
+//!
+//! ```text
+//! $ RUST_LOG_FORMAT='{ts:millis} {level:<5} {target} {file}:{line} - {msg} {kv}' ./main
+//! ```
+//!
+//! See [`PatternFormat`][crate::fmt::PatternFormat] for the placeholders it can contain.
+//!
 //! ## Specifying defaults for environment variables
 //!
 //! `env_logger` can read configuration from environment variables.
@@ -284,12 +296,24 @@
 #![allow(clippy::test_attr_in_doctest)]
 
 mod logger;
+mod panic;
+mod recorder;
 mod writer;
 
+pub mod boost;
+pub mod channel;
+pub mod config;
+pub mod file;
 pub mod fmt;
+#[cfg(unix)]
+pub mod journald;
+pub mod net;
+pub mod reload;
+pub mod syslog;
 
 pub use self::fmt::{Target, TimestampPrecision, WriteStyle};
 pub use self::logger::*;
+pub use self::writer::{Framing, RecordMeta, WriteErrorPolicy, WriteErrors};
 
 #[doc = include_str!("../README.md")]
 #[cfg(doctest)]
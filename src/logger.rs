@@ -1,10 +1,19 @@
-use std::{borrow::Cow, cell::RefCell, env, io};
+use std::{borrow::Cow, cell::RefCell, env, io, sync::Arc, time::Duration};
 
-use log::{LevelFilter, Log, Metadata, Record, SetLoggerError};
+use log::{Level, LevelFilter, Log, Metadata, Record, SetLoggerError};
 
+use crate::boost;
+use crate::channel::RecordSender;
+use crate::config::{Config, ConfigFile, Source};
 use crate::fmt;
 use crate::fmt::{FormatFn, Formatter};
-use crate::writer::{self, Writer};
+#[cfg(unix)]
+use crate::journald::{Journald, JournaldSocket};
+use crate::panic;
+use crate::recorder::FlightRecorder;
+use crate::reload::{FilterHandle, LoggerFilter, SharedFilter};
+use crate::syslog::{Syslog, SyslogSocket};
+use crate::writer::{self, Framing, RecordMeta, WriteErrorPolicy, WriteErrors, Writer};
 
 /// The default name for the environment variable to read filters from.
 pub const DEFAULT_FILTER_ENV: &str = "RUST_LOG";
@@ -12,6 +21,9 @@ pub const DEFAULT_FILTER_ENV: &str = "RUST_LOG";
 /// The default name for the environment variable to read style preferences from.
 pub const DEFAULT_WRITE_STYLE_ENV: &str = "RUST_LOG_STYLE";
 
+/// The default name for the environment variable to read a pattern format from.
+pub const DEFAULT_FORMAT_ENV: &str = "RUST_LOG_FORMAT";
+
 /// `Builder` acts as builder for initializing a `Logger`.
 ///
 /// It can be used to customize the log format, change the environment variable used
@@ -39,6 +51,13 @@ pub struct Builder {
     filter: env_filter::Builder,
     writer: writer::Builder,
     format: fmt::Builder,
+    shared: Option<Arc<SharedFilter>>,
+    journal: Option<bool>,
+    recorder: Option<(usize, LevelFilter)>,
+    recorder_trigger: Option<Level>,
+    channel: Option<RecordSender>,
+    log_panics: bool,
+    panic_backtraces: bool,
     built: bool,
 }
 
@@ -76,7 +95,7 @@ impl Builder {
     /// Initializes the log builder from the environment.
     ///
     /// The variables used to read configuration from can be tweaked before
-    /// passing in.
+    /// passing in. See [`Builder::parse_env`] for how they combine with other settings.
     ///
     /// # Examples
     ///
@@ -115,6 +134,12 @@ impl Builder {
     /// This function allows a builder to be configured with default parameters,
     /// to be then overridden by the environment.
     ///
+    /// The filter and write style replace those set before. The
+    /// [pattern format][fmt::PatternFormat] is only used if no format was set before with
+    /// [`Builder::format`], [`Builder::format_pattern`], [`Builder::syslog`] or
+    /// [`Builder::journald`], so a format the output depends on, like that of syslog,
+    /// isn't replaced. Formats set afterwards replace the pattern.
+    ///
     /// # Examples
     ///
     /// Initialise a logger with filter level `Off`, then override the log
@@ -160,6 +185,13 @@ impl Builder {
             self.parse_write_style(&s);
         }
 
+        // A format set in code, like for syslog, isn't replaced by a pattern
+        if self.format.custom_format.is_none() && self.format.pattern.is_none() {
+            if let Some(s) = env.get_format() {
+                self.parse_format(&s);
+            }
+        }
+
         self
     }
 
@@ -245,9 +277,46 @@ impl Builder {
         F: Fn(&mut Formatter, &Record<'_>) -> io::Result<()> + Sync + Send + 'static,
     {
         self.format.custom_format = Some(Box::new(format));
+        self.format.pattern = None;
         self
     }
 
+    /// Sets a [pattern format][fmt::PatternFormat] for formatting the log output.
+    ///
+    /// The message is written as set with [`Builder::format_sanitize`] and
+    /// [`Builder::format_max_message_len`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use env_logger::fmt::PatternFormat;
+    ///
+    /// let format = PatternFormat::parse("{level:<5} {target} - {msg}").unwrap();
+    ///
+    /// env_logger::Builder::new().format_pattern(format).init();
+    /// ```
+    pub fn format_pattern(&mut self, format: fmt::PatternFormat) -> &mut Self {
+        self.format.pattern = Some(format);
+        self.format.custom_format = None;
+        self
+    }
+
+    /// Parses a [pattern format][fmt::PatternFormat] in the same form as the
+    /// `RUST_LOG_FORMAT` environment variable, and uses it for formatting the log output.
+    ///
+    /// An invalid pattern is reported on stderr and ignored, keeping the current format.
+    pub fn parse_format(&mut self, pattern: &str) -> &mut Self {
+        #![allow(clippy::print_stderr)] // like invalid filters
+
+        match fmt::PatternFormat::parse(pattern) {
+            Ok(format) => self.format_pattern(format),
+            Err(error) => {
+                eprintln!("warning: {error}, ignoring it");
+                self
+            }
+        }
+    }
+
     /// Use the default format.
     ///
     /// This method will clear any custom format set on the builder.
@@ -304,9 +373,46 @@ impl Builder {
         self
     }
 
+    /// Whether or not to escape control characters in the message, like `\n` or `\x1b`.
+    ///
+    /// This keeps messages with untrusted input from writing what looks like other records,
+    /// or terminal escape sequences, while the default format is still styled.
+    ///
+    /// See [`ConfigurableFormat::sanitize`][fmt::ConfigurableFormat::sanitize] for more
+    /// details.
+    pub fn format_sanitize(&mut self, sanitize: bool) -> &mut Self {
+        self.format.default_format.sanitize(sanitize);
+        self
+    }
+
+    /// Configures the number of bytes of the message to write, after which it's cut short
+    /// and marked as `... [truncated]`.
+    /// A value of `None` writes the whole message.
+    pub fn format_max_message_len(&mut self, max_len: Option<usize>) -> &mut Self {
+        self.format.default_format.max_message_len(max_len);
+        self
+    }
+
+    /// Whether or not to format records for the systemd journal.
+    ///
+    /// This is detected by default: when the target is stderr or stdout and systemd has
+    /// connected it to the journal, as told by the `JOURNAL_STREAM` environment variable.
+    ///
+    /// For the journal, the default format starts each line with the priority of the level
+    /// (see [`ConfigurableFormat::priority_prefix`]) and leaves out the timestamp, since
+    /// the journal records its own, unless one was set with [`Builder::format_timestamp`].
+    /// Custom formats are unaffected.
+    ///
+    /// [`ConfigurableFormat::priority_prefix`]: fmt::ConfigurableFormat::priority_prefix
+    pub fn format_journal(&mut self, journal: bool) -> &mut Self {
+        self.journal = Some(journal);
+        self
+    }
+
     /// Configures if timestamp should be included and in what precision.
     pub fn format_timestamp(&mut self, timestamp: Option<fmt::TimestampPrecision>) -> &mut Self {
         self.format.default_format.timestamp(timestamp);
+        self.format.timestamp_set = true;
         self
     }
 
@@ -336,6 +442,12 @@ impl Builder {
         self
     }
 
+    /// Configures an end of line suffix read from a configuration file.
+    pub(crate) fn format_owned_suffix(&mut self, suffix: String) -> &mut Self {
+        self.format.default_format.owned_suffix(suffix);
+        self
+    }
+
     /// Set the format for structured key/value pairs in the log record
     ///
     /// With the default format, this function is called for each record and should format
@@ -424,9 +536,212 @@ impl Builder {
         self
     }
 
+    /// Applies the effective settings of a layered [`Config`].
+    ///
+    /// Each setting with the highest precedence in `config` is applied as if by calling
+    /// the corresponding method, like [`Builder::filter`] or [`Builder::write_style`].
+    ///
+    /// See the [`config`][crate::config] module for more details.
+    pub fn apply_config(&mut self, config: &Config) -> &mut Self {
+        config.apply(self);
+        self
+    }
+
+    /// Applies the settings from a [`ConfigFile`].
+    ///
+    /// Settings left out of the file are unchanged, and filters in the file are added as if
+    /// by [`Builder::parse_filters`].
+    ///
+    /// See [`ConfigFile`] for the file format.
+    pub fn apply_config_file(&mut self, file: &ConfigFile) -> &mut Self {
+        file.apply(self);
+        self
+    }
+
+    /// Sends records to syslog.
+    ///
+    /// This sets the format to `syslog` and the target to `socket`, replacing any custom
+    /// format or target set before.
+    ///
+    /// See the [`syslog`][crate::syslog] module for more details.
+    pub fn syslog(&mut self, syslog: Syslog, socket: SyslogSocket) -> &mut Self {
+        self.format(move |buf, record| syslog.format(buf, record));
+        self.writer.datagram_target(Box::new(socket));
+        self
+    }
+
+    /// Sends records to the systemd journal, with their metadata as separate fields.
+    ///
+    /// This sets the format to `journald` and the target to `socket`, replacing any custom
+    /// format or target set before. The entries are written as-is, regardless of the write
+    /// style.
+    ///
+    /// See the [`journald`][crate::journald] module for more details.
+    #[cfg(unix)]
+    pub fn journald(&mut self, journald: Journald, socket: JournaldSocket) -> &mut Self {
+        self.format(move |buf, record| journald.format(buf, record));
+        self.writer.raw_target(Box::new(socket));
+        self
+    }
+
+    /// Keeps the last `capacity` records up to `level` that the filter leaves out, and
+    /// writes them out when something goes wrong.
+    ///
+    /// The kept records are written before the next record at or above the
+    /// [trigger level][Builder::flight_recorder_trigger], and when the process panics if
+    /// the logger is installed with [`Builder::init`] or [`Builder::try_init`]. This gives
+    /// the context of a failure without the cost of writing everything at `level` all the
+    /// time, though the records are still formatted.
+    ///
+    /// # Examples
+    ///
+    /// Only write debug records leading up to an error:
+    ///
+    /// ```
+    /// use env_logger::Builder;
+    /// use log::LevelFilter;
+    ///
+    /// Builder::new()
+    ///     .filter_level(LevelFilter::Info)
+    ///     .flight_recorder(100, LevelFilter::Debug)
+    ///     .init();
+    /// ```
+    pub fn flight_recorder(&mut self, capacity: usize, level: LevelFilter) -> &mut Self {
+        self.recorder = Some((capacity, level));
+        self
+    }
+
+    /// Sets the level of the records that write out the [flight recorder].
+    ///
+    /// The default is [`Level::Error`].
+    ///
+    /// [flight recorder]: Builder::flight_recorder
+    pub fn flight_recorder_trigger(&mut self, level: Level) -> &mut Self {
+        self.recorder_trigger = Some(level);
+        self
+    }
+
+    /// Sets how records are delimited in the output.
+    ///
+    /// This frames each formatted record, whatever the format, including a custom
+    /// [`Builder::format`]. Frames that carry the length of the record let readers split
+    /// the output into records even when messages contain newlines. A single trailing
+    /// newline, like the one the default format ends records with, is left out of the frame
+    /// except with [`Framing::Newline`].
+    ///
+    /// Styles are adapted to the target before framing, so the frame matches what is
+    /// written. By default, records are written as they're formatted.
+    ///
+    /// Records sent to [syslog][Builder::syslog] or the [journal][Builder::journald] aren't
+    /// framed, since each one is sent in a datagram of its own.
+    ///
+    /// # Examples
+    ///
+    /// Write records as NUL-delimited frames:
+    ///
+    /// ```
+    /// use env_logger::{Builder, Framing};
+    ///
+    /// Builder::new().framing(Framing::Nul).init();
+    /// ```
+    pub fn framing(&mut self, framing: Framing) -> &mut Self {
+        self.writer.framing(framing);
+        self
+    }
+
+    /// Buffers output to `stdout` and `stderr`, writing it out in blocks of up to
+    /// `capacity` bytes instead of one record at a time.
+    ///
+    /// This cuts the cost of writing many records, like when the output is a file. The
+    /// buffer is written out once it's full, when a record at or above the
+    /// [flush level][Builder::flush_level] is logged, once the oldest buffered record has
+    /// waited for the [flush interval][Builder::flush_interval], and when the logger is
+    /// flushed.
+    ///
+    /// The buffer isn't written out on exit, so call [`log::logger().flush()`][log::Log::flush]
+    /// before exiting. Panics flush the logger when it's installed with [`Builder::init`]
+    /// or [`Builder::try_init`] and [logs panics][Builder::log_panics].
+    ///
+    /// Output isn't buffered when [testing][Builder::is_test], or for other targets.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use env_logger::{Builder, Target};
+    ///
+    /// Builder::new()
+    ///     .target(Target::Stdout)
+    ///     .buffered(64 * 1024)
+    ///     .init();
+    ///
+    /// log::info!("many records");
+    ///
+    /// log::logger().flush();
+    /// ```
+    pub fn buffered(&mut self, capacity: usize) -> &mut Self {
+        self.writer.buffered(capacity);
+        self
+    }
+
+    /// Sets how long a record may wait in the [buffer][Builder::buffered].
+    ///
+    /// The buffer is written out by a background thread once its oldest record has waited
+    /// this long, even if no record follows. The default is 1 second.
+    pub fn flush_interval(&mut self, interval: Duration) -> &mut Self {
+        self.writer.flush_interval(interval);
+        self
+    }
+
+    /// Sets the level of the records that write out the [buffer][Builder::buffered] right
+    /// away, along with the records before them.
+    ///
+    /// The default is [`Level::Error`].
+    pub fn flush_level(&mut self, level: Level) -> &mut Self {
+        self.writer.flush_level(level);
+        self
+    }
+
+    /// Sends records to a channel instead of writing them to the target.
+    ///
+    /// Each record is sent as an [`OwnedRecord`][crate::channel::OwnedRecord], without
+    /// being formatted. The [flight recorder][Builder::flight_recorder] still writes to
+    /// the target.
+    ///
+    /// See the [`channel`][crate::channel] module for more details.
+    pub fn channel(&mut self, sender: impl Into<RecordSender>) -> &mut Self {
+        self.channel = Some(sender.into());
+        self
+    }
+
+    /// Whether or not to log panics.
+    ///
+    /// When the logger is installed with [`Builder::init`] or [`Builder::try_init`], this
+    /// installs a panic hook that logs each panic as an `error` record with the `panic`
+    /// target, before calling the previous hook. The record has the message, location and
+    /// thread name of the panic, and a backtrace if [`Builder::log_panic_backtraces`] is
+    /// set.
+    ///
+    /// Panics are written whatever the filter, so they aren't lost when only some modules
+    /// are logged, like with `RUST_LOG=myapp=debug`.
+    ///
+    /// This is off by default.
+    pub fn log_panics(&mut self, log: bool) -> &mut Self {
+        self.log_panics = log;
+        self
+    }
+
+    /// Whether or not to capture a backtrace for each [logged panic][Builder::log_panics].
+    ///
+    /// Backtraces are captured regardless of `RUST_BACKTRACE`. This is off by default.
+    pub fn log_panic_backtraces(&mut self, capture: bool) -> &mut Self {
+        self.panic_backtraces = capture;
+        self
+    }
+
     /// Sets the target for the log output.
     ///
-    /// Env logger can log to either stdout, stderr or a custom pipe. The default is stderr.
+    /// Env logger can log to either stdout, stderr, both split by level, or a custom pipe.
+    /// The default is stderr.
     ///
     /// The custom pipe can be used to send the log messages to a custom sink (for example a file).
     /// Do note that direct writes to a file can become a bottleneck due to IO operation times.
@@ -486,6 +801,51 @@ impl Builder {
         self
     }
 
+    /// Sets what to do when writing a record to the target fails.
+    ///
+    /// The default is to drop the record. Failures are counted either way, see
+    /// [`Builder::write_errors`].
+    ///
+    /// # Examples
+    ///
+    /// Write records to stderr while the file they're written to can't be written:
+    ///
+    /// ```no_run
+    /// use env_logger::{Builder, Target, WriteErrorPolicy};
+    ///
+    /// let file = std::fs::File::create("app.log")?;
+    /// Builder::from_default_env()
+    ///     .target(Target::Pipe(Box::new(file)))
+    ///     .write_error_policy(WriteErrorPolicy::Fallback(Target::Stderr))
+    ///     .init();
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn write_error_policy(&mut self, policy: WriteErrorPolicy) -> &mut Self {
+        self.writer.error_policy(policy);
+        self
+    }
+
+    /// Returns the write errors of the logger once it's built.
+    pub fn write_errors(&self) -> WriteErrors {
+        self.writer.errors().clone()
+    }
+
+    /// Returns a handle to change the filter of the logger once it's built.
+    ///
+    /// The handle replaces the filter configured on this builder as soon as the
+    /// logger is built, including by [`Builder::init`].
+    ///
+    /// A logger built without a handle checks its filter without any synchronization,
+    /// so only take a handle if the filter needs to change.
+    ///
+    /// See the [`reload`][crate::reload] module for more details.
+    pub fn filter_handle(&mut self) -> FilterHandle {
+        let shared = self
+            .shared
+            .get_or_insert_with(|| Arc::new(SharedFilter::new(env_filter::Builder::new().build())));
+        FilterHandle::new(shared.clone())
+    }
+
     /// Initializes the global logger with the built env logger.
     ///
     /// This should be called early in the execution of a Rust program. Any log
@@ -496,13 +856,21 @@ impl Builder {
     /// This function will fail if it is called more than once, or if another
     /// library has already initialized a global logger.
     pub fn try_init(&mut self) -> Result<(), SetLoggerError> {
-        let logger = self.build();
+        let logger = Arc::new(self.build());
 
-        let max_level = logger.filter();
-        let r = log::set_boxed_logger(Box::new(logger));
+        let hook = panic::Hook {
+            log: self.log_panics,
+            backtrace: self.panic_backtraces,
+        };
+        let needs_hook = hook.log || logger.recorder.is_some();
+        // The logger is only leaked if it's set, and shared with the panic hook
+        let r = log::set_boxed_logger(Box::new(GlobalLogger(logger.clone())));
 
         if r.is_ok() {
-            log::set_max_level(max_level);
+            logger.filter.set_global();
+            if needs_hook {
+                hook.install(logger);
+            }
         }
 
         r
@@ -530,10 +898,35 @@ impl Builder {
         assert!(!self.built, "attempt to re-use consumed builder");
         self.built = true;
 
+        if self
+            .journal
+            .unwrap_or_else(|| self.writer.is_journal_stream())
+        {
+            self.format.default_format.priority_prefix(true);
+            if !self.format.timestamp_set {
+                self.format.default_format.timestamp(None);
+            }
+        }
+
+        let mut filter = LoggerFilter::new(self.filter.build(), self.shared.take());
+
+        let recorder = self.recorder.map(|(capacity, level)| {
+            FlightRecorder::new(
+                capacity,
+                level,
+                self.recorder_trigger.unwrap_or(Level::Error),
+            )
+        });
+        if let Some(recorder) = &recorder {
+            filter.set_floor(recorder.level());
+        }
+
         Logger {
             writer: self.writer.build(),
-            filter: self.filter.build(),
+            filter,
             format: self.format.build(),
+            recorder,
+            channel: self.channel.take(),
         }
     }
 }
@@ -572,8 +965,10 @@ impl std::fmt::Debug for Builder {
 /// [`Builder`]: struct.Builder.html
 pub struct Logger {
     writer: Writer,
-    filter: env_filter::Filter,
+    filter: LoggerFilter,
     format: FormatFn,
+    recorder: Option<FlightRecorder>,
+    channel: Option<RecordSender>,
 }
 
 impl Logger {
@@ -634,83 +1029,219 @@ impl Logger {
     /// Returns the maximum `LevelFilter` that this env logger instance is
     /// configured to output.
     pub fn filter(&self) -> LevelFilter {
-        self.filter.filter()
+        self.filter.with(env_filter::Filter::filter)
     }
 
     /// Checks if this record matches the configured filter.
+    ///
+    /// Records at or below the level of a [boost][crate::boost] on the current thread
+    /// always match.
     pub fn matches(&self, record: &Record<'_>) -> bool {
-        self.filter.matches(record)
+        boost::enabled(record.level()) || self.filter.with(|filter| filter.matches(record))
+    }
+
+    /// Returns the write errors of this logger.
+    pub fn write_errors(&self) -> WriteErrors {
+        self.writer.errors().clone()
     }
 }
 
 impl Log for Logger {
     fn enabled(&self, metadata: &Metadata<'_>) -> bool {
-        self.filter.enabled(metadata)
+        boost::enabled(metadata.level())
+            || self.filter.with(|filter| filter.enabled(metadata))
+            || self
+                .recorder
+                .as_ref()
+                .is_some_and(|recorder| recorder.records(metadata.level()))
+    }
+
+    fn log(&self, record: &Record<'_>) {
+        if boost::enabled(record.level()) {
+            self.print(record);
+            return;
+        }
+
+        self.filter.with(|filter| self.log_filtered(filter, record));
+    }
+
+    fn flush(&self) {
+        let _ = self.writer.flush();
+    }
+}
+
+/// The global logger, which shares the [`Logger`] with the panic hook.
+struct GlobalLogger(Arc<Logger>);
+
+impl Log for GlobalLogger {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        self.0.enabled(metadata)
     }
 
     fn log(&self, record: &Record<'_>) {
-        if self.matches(record) {
-            // Log records are written to a thread-local buffer before being printed
-            // to the terminal. We clear these buffers afterwards, but they aren't shrunk
-            // so will always at least have capacity for the largest log record formatted
-            // on that thread.
-            //
-            // If multiple `Logger`s are used by the same threads then the thread-local
-            // formatter might have different color support. If this is the case the
-            // formatter and its buffer are discarded and recreated.
-
-            thread_local! {
-                static FORMATTER: RefCell<Option<Formatter>> = const { RefCell::new(None) };
+        self.0.log(record);
+    }
+
+    fn flush(&self) {
+        self.0.flush();
+    }
+}
+
+impl Logger {
+    fn log_filtered(&self, filter: &env_filter::Filter, record: &Record<'_>) {
+        if !filter.enabled(record.metadata()) {
+            if let Some(recorder) = &self.recorder {
+                if recorder.records(record.level()) {
+                    self.with_formatter(|formatter| {
+                        if self.format.format(formatter, record).is_ok() {
+                            formatter.with_bytes(|bytes| {
+                                recorder.push(&RecordMeta::new(record), bytes);
+                            });
+                        }
+                    });
+                }
+            }
+        } else if filter.has_message_filter() && record.args().as_str().is_none() {
+            self.log_formatted_message(filter, record);
+        } else if filter.matches(record) {
+            self.print(record);
+        }
+    }
+
+    /// Format the message once, for both the message filter and the output.
+    fn log_formatted_message(&self, filter: &env_filter::Filter, record: &Record<'_>) {
+        // The message is written to its own thread-local buffer, which is cleared
+        // but not shrunk afterwards, just like the formatter's buffer in `print`.
+
+        thread_local! {
+            static MESSAGE: RefCell<String> = const { RefCell::new(String::new()) };
+        }
+
+        let log = |message: &mut String| {
+            use std::fmt::Write as _;
+
+            if write!(message, "{}", record.args()).is_ok()
+                && filter.matches_message(record, message)
+            {
+                let mut builder = Record::builder();
+                builder
+                    .metadata(record.metadata().clone())
+                    .module_path(record.module_path())
+                    .file(record.file())
+                    .line(record.line());
+                #[cfg(feature = "kv")]
+                builder.key_values(record.key_values());
+
+                self.print(&builder.args(format_args!("{message}")).build());
             }
 
-            let print = |formatter: &mut Formatter, record: &Record<'_>| {
-                let _ = self
-                    .format
-                    .format(formatter, record)
-                    .and_then(|_| formatter.print(&self.writer));
+            // Always clear the buffer afterwards
+            message.clear();
+        };
+
+        let logged = MESSAGE
+            .try_with(|tl_buf| {
+                if let Ok(mut tl_buf) = tl_buf.try_borrow_mut() {
+                    log(&mut tl_buf);
+                } else {
+                    // There's already an active borrow of the buffer (due to re-entrancy)
+                    log(&mut String::new());
+                }
+            })
+            .is_ok();
+
+        if !logged {
+            // The thread-local storage was not available (because its
+            // destructor has already run).
+            log(&mut String::new());
+        }
+    }
 
-                // Always clear the buffer afterwards
-                formatter.clear();
-            };
+    /// Writes out the records kept by the flight recorder, if any.
+    pub(crate) fn dump_recorder(&self) {
+        if let Some(recorder) = &self.recorder {
+            recorder.dump(&self.writer);
+        }
+    }
+
+    /// Writes out a record without checking the filter.
+    pub(crate) fn print(&self, record: &Record<'_>) {
+        if let Some(recorder) = &self.recorder {
+            if recorder.triggers(record.level()) {
+                recorder.dump(&self.writer);
+            }
+        }
+
+        if let Some(channel) = &self.channel {
+            channel.send(record);
+            return;
+        }
+
+        self.with_formatter(|formatter| {
+            let _ = self
+                .format
+                .format(formatter, record)
+                .and_then(|_| formatter.print(&self.writer, &RecordMeta::new(record)));
+        });
+    }
+
+    /// Calls `f` with a formatter for this logger, which is cleared afterwards.
+    fn with_formatter(&self, f: impl Fn(&mut Formatter)) {
+        // Log records are written to a thread-local buffer before being printed
+        // to the terminal. We clear these buffers afterwards, but they aren't shrunk
+        // so will always at least have capacity for the largest log record formatted
+        // on that thread.
+        //
+        // If multiple `Logger`s are used by the same threads then the thread-local
+        // formatter might have different color support. If this is the case the
+        // formatter and its buffer are discarded and recreated.
+
+        thread_local! {
+            static FORMATTER: RefCell<Option<Formatter>> = const { RefCell::new(None) };
+        }
+
+        let print = |formatter: &mut Formatter| {
+            f(formatter);
+
+            // Always clear the buffer afterwards
+            formatter.clear();
+        };
+
+        let printed = FORMATTER
+            .try_with(|tl_buf| {
+                if let Ok(mut tl_buf) = tl_buf.try_borrow_mut() {
+                    // There are no active borrows of the buffer
+                    if let Some(ref mut formatter) = *tl_buf {
+                        // We have a previously set formatter
 
-            let printed = FORMATTER
-                .try_with(|tl_buf| {
-                    if let Ok(mut tl_buf) = tl_buf.try_borrow_mut() {
-                        // There are no active borrows of the buffer
-                        if let Some(ref mut formatter) = *tl_buf {
-                            // We have a previously set formatter
-
-                            // Check the buffer style. If it's different from the logger's
-                            // style then drop the buffer and recreate it.
-                            if formatter.write_style() != self.writer.write_style() {
-                                *formatter = Formatter::new(&self.writer);
-                            }
-
-                            print(formatter, record);
-                        } else {
-                            // We don't have a previously set formatter
-                            let mut formatter = Formatter::new(&self.writer);
-                            print(&mut formatter, record);
-
-                            *tl_buf = Some(formatter);
+                        // Check the buffer style. If it's different from the logger's
+                        // style then drop the buffer and recreate it.
+                        if formatter.write_style() != self.writer.write_style() {
+                            *formatter = Formatter::new(&self.writer);
                         }
+
+                        print(formatter);
                     } else {
-                        // There's already an active borrow of the buffer (due to re-entrancy)
-                        print(&mut Formatter::new(&self.writer), record);
+                        // We don't have a previously set formatter
+                        let mut formatter = Formatter::new(&self.writer);
+                        print(&mut formatter);
+
+                        *tl_buf = Some(formatter);
                     }
-                })
-                .is_ok();
-
-            if !printed {
-                // The thread-local storage was not available (because its
-                // destructor has already run). Create a new single-use
-                // Formatter on the stack for this call.
-                print(&mut Formatter::new(&self.writer), record);
-            }
+                } else {
+                    // There's already an active borrow of the buffer (due to re-entrancy)
+                    print(&mut Formatter::new(&self.writer));
+                }
+            })
+            .is_ok();
+
+        if !printed {
+            // The thread-local storage was not available (because its
+            // destructor has already run). Create a new single-use
+            // Formatter on the stack for this call.
+            print(&mut Formatter::new(&self.writer));
         }
     }
-
-    fn flush(&self) {}
 }
 
 impl std::fmt::Debug for Logger {
@@ -729,12 +1260,14 @@ impl std::fmt::Debug for Logger {
 ///
 /// - `RUST_LOG`: the level filter
 /// - `RUST_LOG_STYLE`: whether or not to print styles with records.
+/// - `RUST_LOG_FORMAT`: a [pattern format][fmt::PatternFormat] for records.
 ///
 /// These sources can be configured using the builder methods on `Env`.
 #[derive(Debug)]
 pub struct Env<'a> {
     filter: Var<'a>,
     write_style: Var<'a>,
+    format: Var<'a>,
 }
 
 impl<'a> Env<'a> {
@@ -782,6 +1315,10 @@ impl<'a> Env<'a> {
         self.filter.get()
     }
 
+    pub(crate) fn get_filter_with_source(&self) -> Option<(String, Source)> {
+        self.filter.get_with_source()
+    }
+
     /// Specify an environment variable to read the style from.
     pub fn write_style<E>(mut self, write_style_env: E) -> Self
     where
@@ -820,6 +1357,49 @@ impl<'a> Env<'a> {
     fn get_write_style(&self) -> Option<String> {
         self.write_style.get()
     }
+
+    pub(crate) fn get_write_style_with_source(&self) -> Option<(String, Source)> {
+        self.write_style.get_with_source()
+    }
+
+    /// Specify an environment variable to read a pattern format from.
+    pub fn format<E>(mut self, format_env: E) -> Self
+    where
+        E: Into<Cow<'a, str>>,
+    {
+        self.format = Var::new(format_env);
+
+        self
+    }
+
+    /// Specify an environment variable to read a pattern format from.
+    ///
+    /// If the variable is not set, the default value will be used.
+    pub fn format_or<E, V>(mut self, format_env: E, default: V) -> Self
+    where
+        E: Into<Cow<'a, str>>,
+        V: Into<Cow<'a, str>>,
+    {
+        self.format = Var::new_with_default(format_env, default);
+
+        self
+    }
+
+    /// Use the default environment variable to read a pattern format from.
+    ///
+    /// If the variable is not set, the default value will be used.
+    pub fn default_format_or<V>(mut self, default: V) -> Self
+    where
+        V: Into<Cow<'a, str>>,
+    {
+        self.format = Var::new_with_default(DEFAULT_FORMAT_ENV, default);
+
+        self
+    }
+
+    fn get_format(&self) -> Option<String> {
+        self.format.get()
+    }
 }
 
 impl<'a, T> From<T> for Env<'a>
@@ -836,6 +1416,7 @@ impl Default for Env<'_> {
         Env {
             filter: Var::new(DEFAULT_FILTER_ENV),
             write_style: Var::new(DEFAULT_WRITE_STYLE_ENV),
+            format: Var::new(DEFAULT_FORMAT_ENV),
         }
     }
 }
@@ -873,6 +1454,16 @@ impl<'a> Var<'a> {
             .ok()
             .or_else(|| self.default.clone().map(|v| v.into_owned()))
     }
+
+    fn get_with_source(&self) -> Option<(String, Source)> {
+        match env::var(&*self.name) {
+            Ok(v) => Some((v, Source::env(&*self.name))),
+            Err(_) => self
+                .default
+                .clone()
+                .map(|v| (v.into_owned(), Source::code(&*self.name))),
+        }
+    }
 }
 
 /// Attempts to initialize the global logger with an env logger.
@@ -997,6 +1588,8 @@ where
 mod tests {
     use super::*;
 
+    use std::io::Write as _;
+
     #[test]
     fn env_get_filter_reads_from_var_if_set() {
         env::set_var("env_get_filter_reads_from_var_if_set", "from var");
@@ -1040,6 +1633,388 @@ mod tests {
         assert_eq!(Some("from default".to_owned()), env.get_write_style());
     }
 
+    #[test]
+    fn env_get_format_reads_from_var_if_set() {
+        env::set_var("env_get_format_reads_from_var_if_set", "from var");
+
+        let env = Env::new().format_or("env_get_format_reads_from_var_if_set", "from default");
+
+        assert_eq!(Some("from var".to_owned()), env.get_format());
+    }
+
+    #[derive(Clone, Default)]
+    struct Pipe(Arc<std::sync::Mutex<Vec<u8>>>);
+
+    impl io::Write for Pipe {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl Pipe {
+        fn contents(&self) -> String {
+            String::from_utf8(self.0.lock().unwrap().clone()).unwrap()
+        }
+    }
+
+    #[test]
+    fn log_formats_message_once_for_message_filter() {
+        use std::cell::Cell;
+
+        struct Counted<'a>(&'a Cell<usize>);
+
+        impl std::fmt::Display for Counted<'_> {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                self.0.set(self.0.get() + 1);
+                f.write_str("foo")
+            }
+        }
+
+        let pipe = Pipe::default();
+        let logger = Builder::new()
+            .parse_filters("info/foo")
+            .format(|buf, record| writeln!(buf, "{}", record.args()))
+            .target(fmt::Target::Pipe(Box::new(pipe.clone())))
+            .build();
+
+        let count = Cell::new(0);
+        let log = |args: std::fmt::Arguments<'_>| {
+            logger.log(&Record::builder().level(Level::Info).args(args).build());
+        };
+        log(format_args!("{} 1", Counted(&count)));
+        log(format_args!("bar {}", 2));
+        log(format_args!("foo 3"));
+
+        assert_eq!(count.get(), 1);
+        assert_eq!(pipe.contents(), "foo 1\nfoo 3\n");
+    }
+
+    #[test]
+    fn format_journal_overrides_detection() {
+        let pipe = Pipe::default();
+        let logger = Builder::new()
+            .filter_level(LevelFilter::Info)
+            .format_journal(true)
+            .format_target(false)
+            .target(fmt::Target::Pipe(Box::new(pipe.clone())))
+            .build();
+
+        logger.log(
+            &Record::builder()
+                .level(Level::Warn)
+                .args(format_args!("disk almost full"))
+                .build(),
+        );
+
+        assert_eq!(pipe.contents(), "<4>[WARN ] disk almost full\n");
+    }
+
+    #[test]
+    #[cfg(feature = "humantime")]
+    fn format_journal_keeps_timestamp_set() {
+        let pipe = Pipe::default();
+        let logger = Builder::new()
+            .filter_level(LevelFilter::Info)
+            .format_timestamp_secs()
+            .format_journal(true)
+            .format_target(false)
+            .target(fmt::Target::Pipe(Box::new(pipe.clone())))
+            .build();
+
+        log_info(&logger, "started");
+
+        let contents = pipe.contents();
+        assert!(contents.starts_with("<6>[20"), "{contents:?}");
+        assert!(contents.ends_with("Z INFO ] started\n"), "{contents:?}");
+    }
+
+    #[test]
+    fn flight_recorder_writes_records_before_error() {
+        let pipe = Pipe::default();
+        let logger = Builder::new()
+            .filter_level(LevelFilter::Info)
+            .flight_recorder(2, LevelFilter::Debug)
+            .format(|buf, record| writeln!(buf, "{}", record.args()))
+            .target(fmt::Target::Pipe(Box::new(pipe.clone())))
+            .build();
+        assert!(logger.enabled(&Metadata::builder().level(Level::Debug).build()));
+        assert!(!logger.enabled(&Metadata::builder().level(Level::Trace).build()));
+
+        let log = |level, args: std::fmt::Arguments<'_>| {
+            logger.log(&Record::builder().level(level).args(args).build());
+        };
+        log(Level::Debug, format_args!("dropped"));
+        log(Level::Trace, format_args!("ignored"));
+        log(Level::Debug, format_args!("first"));
+        log(Level::Warn, format_args!("warn"));
+        log(Level::Debug, format_args!("second"));
+        log(Level::Error, format_args!("error"));
+        log(Level::Error, format_args!("error again"));
+
+        assert_eq!(pipe.contents(), "warn\nfirst\nsecond\nerror\nerror again\n");
+    }
+
+    #[test]
+    fn flight_recorder_trigger_level() {
+        let pipe = Pipe::default();
+        let logger = Builder::new()
+            .filter_level(LevelFilter::Info)
+            .flight_recorder(10, LevelFilter::Trace)
+            .flight_recorder_trigger(Level::Warn)
+            .format(|buf, record| writeln!(buf, "{}", record.args()))
+            .target(fmt::Target::Pipe(Box::new(pipe.clone())))
+            .build();
+
+        for (level, message) in [
+            (Level::Trace, "trace"),
+            (Level::Info, "info"),
+            (Level::Warn, "warn"),
+        ] {
+            logger.log(
+                &Record::builder()
+                    .level(level)
+                    .args(format_args!("{message}"))
+                    .build(),
+            );
+        }
+
+        assert_eq!(pipe.contents(), "info\ntrace\nwarn\n");
+    }
+
+    /// A pipe that fails every write, counting them.
+    #[derive(Clone, Default)]
+    struct BrokenPipe(Arc<std::sync::atomic::AtomicUsize>);
+
+    impl io::Write for BrokenPipe {
+        fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+            self.0.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            Err(io::ErrorKind::BrokenPipe.into())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl BrokenPipe {
+        fn writes(&self) -> usize {
+            self.0.load(std::sync::atomic::Ordering::Relaxed)
+        }
+    }
+
+    fn log_info(logger: &Logger, message: &str) {
+        logger.log(
+            &Record::builder()
+                .level(Level::Info)
+                .args(format_args!("{message}"))
+                .build(),
+        );
+    }
+
+    #[test]
+    fn write_errors_are_counted() {
+        let pipe = BrokenPipe::default();
+        let errors = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let logger = {
+            let errors = errors.clone();
+            Builder::new()
+                .filter_level(LevelFilter::Info)
+                .target(fmt::Target::Pipe(Box::new(pipe.clone())))
+                .write_error_policy(WriteErrorPolicy::callback(move |err| {
+                    errors.lock().unwrap().push(err.kind());
+                }))
+                .build()
+        };
+
+        log_info(&logger, "first");
+        log_info(&logger, "second");
+
+        assert_eq!(logger.write_errors().count(), 2);
+        assert!(!logger.write_errors().is_disabled());
+        assert_eq!(
+            *errors.lock().unwrap(),
+            [io::ErrorKind::BrokenPipe, io::ErrorKind::BrokenPipe]
+        );
+    }
+
+    #[test]
+    fn write_errors_fall_back() {
+        let fallback = Pipe::default();
+        let mut builder = Builder::new();
+        builder
+            .filter_level(LevelFilter::Info)
+            .format(|buf, record| writeln!(buf, "{}", record.args()))
+            .target(fmt::Target::Pipe(Box::new(BrokenPipe::default())))
+            .write_error_policy(WriteErrorPolicy::Fallback(fmt::Target::Pipe(Box::new(
+                fallback.clone(),
+            ))));
+        let errors = builder.write_errors();
+        let logger = builder.build();
+
+        log_info(&logger, "first");
+
+        assert_eq!(fallback.contents(), "first\n");
+        assert_eq!(errors.count(), 1);
+    }
+
+    #[test]
+    fn write_errors_disable_writing() {
+        let pipe = BrokenPipe::default();
+        let logger = Builder::new()
+            .filter_level(LevelFilter::Info)
+            .target(fmt::Target::Pipe(Box::new(pipe.clone())))
+            .write_error_policy(WriteErrorPolicy::DisableAfter(2))
+            .build();
+        let errors = logger.write_errors();
+
+        for _ in 0..3 {
+            log_info(&logger, "message");
+        }
+        assert_eq!(pipe.writes(), 2);
+        assert_eq!(errors.count(), 2);
+        assert!(errors.is_disabled());
+
+        errors.enable();
+        log_info(&logger, "message");
+        assert_eq!(pipe.writes(), 3);
+        assert!(!errors.is_disabled());
+    }
+
+    #[test]
+    fn callback_target_gets_metadata() {
+        let records = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let logger = {
+            let records = records.clone();
+            Builder::new()
+                .filter_level(LevelFilter::Info)
+                .format(|buf, record| writeln!(buf, "{}", record.args()))
+                .target(fmt::Target::Callback(Box::new(move |bytes, meta| {
+                    records.lock().unwrap().push((
+                        String::from_utf8(bytes.to_vec()).unwrap(),
+                        meta.level(),
+                        meta.target().to_owned(),
+                        meta.file().map(str::to_owned),
+                        meta.line(),
+                    ));
+                })))
+                .build()
+        };
+
+        logger.log(
+            &Record::builder()
+                .level(Level::Warn)
+                .target("app::db")
+                .file(Some("src/db.rs"))
+                .line(Some(7))
+                .args(format_args!("slow"))
+                .build(),
+        );
+
+        assert_eq!(
+            *records.lock().unwrap(),
+            [(
+                "slow\n".to_owned(),
+                Level::Warn,
+                "app::db".to_owned(),
+                Some("src/db.rs".to_owned()),
+                Some(7)
+            )]
+        );
+    }
+
+    #[test]
+    fn channel_gets_matching_records() {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let logger = Builder::new()
+            .filter_level(LevelFilter::Info)
+            .channel(sender)
+            .build();
+
+        for level in [Level::Debug, Level::Warn] {
+            logger.log(
+                &Record::builder()
+                    .level(level)
+                    .args(format_args!("{level}"))
+                    .build(),
+            );
+        }
+
+        let messages: Vec<_> = receiver
+            .try_iter()
+            .map(|record| record.message().to_owned())
+            .collect();
+        assert_eq!(messages, ["WARN"]);
+    }
+
+    #[test]
+    #[cfg(feature = "color")]
+    fn callback_target_follows_write_style() {
+        for (write_style, styled) in [
+            (fmt::WriteStyle::Always, true),
+            (fmt::WriteStyle::Never, false),
+        ] {
+            let styled_bytes = Arc::new(std::sync::Mutex::new(None));
+            let logger = {
+                let styled_bytes = styled_bytes.clone();
+                Builder::new()
+                    .filter_level(LevelFilter::Info)
+                    .write_style(write_style)
+                    .target(fmt::Target::Callback(Box::new(move |bytes, _| {
+                        *styled_bytes.lock().unwrap() = Some(bytes.contains(&b'\x1b'));
+                    })))
+                    .build()
+            };
+            log_info(&logger, "message");
+
+            assert_eq!(*styled_bytes.lock().unwrap(), Some(styled));
+        }
+    }
+
+    #[test]
+    fn framing_applies_to_custom_format() {
+        let frames = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let logger = {
+            let frames = frames.clone();
+            Builder::new()
+                .filter_level(LevelFilter::Info)
+                .write_style(fmt::WriteStyle::Never)
+                .format(|buf, record| writeln!(buf, "{}", record.args()))
+                .framing(Framing::LengthPrefixed)
+                .target(fmt::Target::Callback(Box::new(move |bytes, _| {
+                    frames.lock().unwrap().push(bytes.to_vec());
+                })))
+                .build()
+        };
+        log_info(&logger, "multi\nline");
+
+        assert_eq!(*frames.lock().unwrap(), [b"\0\0\0\x0amulti\nline"]);
+    }
+
+    #[test]
+    #[cfg(feature = "color")]
+    fn framing_counts_adapted_styles() {
+        let frames = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let logger = {
+            let frames = frames.clone();
+            Builder::new()
+                .filter_level(LevelFilter::Info)
+                .write_style(fmt::WriteStyle::Never)
+                .format(|buf, record| writeln!(buf, "\x1b[1m{}\x1b[0m", record.args()))
+                .framing(Framing::OctetCounting)
+                .target(fmt::Target::Callback(Box::new(move |bytes, _| {
+                    frames.lock().unwrap().push(bytes.to_vec());
+                })))
+                .build()
+        };
+        log_info(&logger, "bold");
+
+        assert_eq!(*frames.lock().unwrap(), [b"4 bold"]);
+    }
+
     #[test]
     fn builder_parse_env_overrides_existing_filters() {
         env::set_var(
@@ -1055,4 +2030,154 @@ mod tests {
 
         assert_eq!(builder.filter.build().filter(), LevelFilter::Debug);
     }
+
+    #[test]
+    fn builder_parse_env_reads_pattern_format() {
+        env::set_var(
+            "builder_parse_env_reads_pattern_format",
+            "{level} {target}: {msg}",
+        );
+        let env = Env::new().format("builder_parse_env_reads_pattern_format");
+
+        let records = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let logger = {
+            let records = records.clone();
+            Builder::new()
+                .filter_level(LevelFilter::Info)
+                .write_style(fmt::WriteStyle::Never)
+                .parse_env(env)
+                .target(fmt::Target::Callback(Box::new(move |bytes, _| {
+                    records.lock().unwrap().push(bytes.to_vec());
+                })))
+                .build()
+        };
+        logger.log(
+            &Record::builder()
+                .level(Level::Warn)
+                .target("app")
+                .args(format_args!("patterned"))
+                .build(),
+        );
+
+        assert_eq!(*records.lock().unwrap(), [b"WARN app: patterned\n"]);
+    }
+
+    #[test]
+    fn builder_pattern_format_sanitizes_and_truncates() {
+        let records = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let logger = {
+            let records = records.clone();
+            Builder::new()
+                .filter_level(LevelFilter::Info)
+                .write_style(fmt::WriteStyle::Never)
+                .format_sanitize(true)
+                .parse_format("{level}: {msg}")
+                .format_max_message_len(Some(12))
+                .target(fmt::Target::Callback(Box::new(move |bytes, _| {
+                    records.lock().unwrap().push(bytes.to_vec());
+                })))
+                .build()
+        };
+        log_info(&logger, "user\n[ERROR] fake");
+
+        assert_eq!(
+            *records.lock().unwrap(),
+            [b"INFO: user\\n[ERROR... [truncated]\n"]
+        );
+    }
+
+    #[test]
+    fn builder_parse_env_keeps_custom_format() {
+        env::set_var("builder_parse_env_keeps_custom_format", "{level} {msg}");
+        let env = || Env::new().format("builder_parse_env_keeps_custom_format");
+
+        let log = |builder: &mut Builder| {
+            let records = Arc::new(std::sync::Mutex::new(Vec::new()));
+            let logger = {
+                let records = records.clone();
+                builder
+                    .filter_level(LevelFilter::Info)
+                    .write_style(fmt::WriteStyle::Never)
+                    .parse_env(env())
+                    .target(fmt::Target::Callback(Box::new(move |bytes, _| {
+                        records.lock().unwrap().push(bytes.to_vec());
+                    })))
+                    .build()
+            };
+            log_info(&logger, "message");
+            let records = records.lock().unwrap();
+            String::from_utf8(records.concat()).unwrap()
+        };
+
+        assert_eq!(
+            log(Builder::new().format(|buf, record| writeln!(buf, "custom: {}", record.args()))),
+            "custom: message\n"
+        );
+        assert_eq!(log(Builder::new().parse_format("{msg}")), "message\n");
+        assert_eq!(log(&mut Builder::new()), "INFO message\n");
+    }
+
+    #[test]
+    fn builder_parse_env_keeps_syslog_format() {
+        use std::net::UdpSocket;
+
+        env::set_var("builder_parse_env_keeps_syslog_format", "{level} {msg}");
+        let env = Env::new().format("builder_parse_env_keeps_syslog_format");
+
+        let server = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let socket = SyslogSocket::udp(server.local_addr().unwrap()).unwrap();
+        let logger = Builder::new()
+            .filter_level(LevelFilter::Info)
+            .syslog(Syslog::new(), socket)
+            .parse_env(env)
+            .build();
+        log_info(&logger, "message");
+
+        let mut buf = [0; 256];
+        let len = server.recv(&mut buf).unwrap();
+        let message = std::str::from_utf8(&buf[..len]).unwrap();
+        assert!(message.starts_with("<14>1 "), "{message}");
+        assert!(message.ends_with(" message"), "{message}");
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn builder_parse_env_keeps_journald_format() {
+        use std::os::unix::net::UnixDatagram;
+
+        env::set_var("builder_parse_env_keeps_journald_format", "{level} {msg}");
+        let env = Env::new().format("builder_parse_env_keeps_journald_format");
+
+        let path = env::temp_dir().join(format!(
+            "env_logger-parse-env-journald-{}",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&path);
+        let server = UnixDatagram::bind(&path).unwrap();
+
+        let logger = Builder::new()
+            .filter_level(LevelFilter::Info)
+            .journald(Journald::new(), JournaldSocket::connect(&path).unwrap())
+            .parse_env(env)
+            .build();
+        log_info(&logger, "message");
+
+        let mut buf = [0; 256];
+        let len = server.recv(&mut buf).unwrap();
+        let entry = std::str::from_utf8(&buf[..len]).unwrap();
+        assert!(
+            entry.starts_with("MESSAGE=message\nPRIORITY=6\n"),
+            "{entry}"
+        );
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn builder_parse_format_ignores_invalid_patterns() {
+        let mut builder = Builder::new();
+        builder.parse_format("{msg}").parse_format("{unknown}");
+
+        assert!(builder.format.pattern.is_some());
+    }
 }
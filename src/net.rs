@@ -0,0 +1,431 @@
+//! Sending records over the network.
+//!
+//! A [`NetworkTarget`] sends records to a TCP or UDP address, like a local log relay. It's
+//! used as a [`Target::Pipe`]:
+//!
+//! ```no_run
+//! use env_logger::net::{Framing, NetworkTarget};
+//! use env_logger::Target;
+//!
+//! let mut relay = NetworkTarget::tcp("127.0.0.1:9000")?;
+//! relay.framing(Framing::OctetCounting);
+//!
+//! env_logger::Builder::from_default_env()
+//!     .target(Target::Pipe(Box::new(relay)))
+//!     .init();
+//! # Ok::<(), std::io::Error>(())
+//! ```
+//!
+//! Writing a record only adds it to a queue. A background thread, started with the first
+//! record, connects and sends the queued records, so logging threads never wait for the
+//! network, even when the other end is slow. When connecting fails or the connection
+//! breaks, the thread waits before reconnecting, doubling the delay after each failed
+//! attempt. Once the queue is full, the oldest records are dropped.
+//!
+//! Writes to the target never fail, so a broken connection doesn't lose records that fit
+//! in the queue. A record that couldn't be sent whole is sent again on the next
+//! connection. However, TCP only reports a broken connection on a later write, so the
+//! records sent just before a connection breaks may be lost.
+//!
+//! [`Target::Pipe`]: crate::Target::Pipe
+
+use std::collections::VecDeque;
+use std::io::{self, Write as _};
+use std::net::{SocketAddr, TcpStream, ToSocketAddrs, UdpSocket};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+pub use crate::writer::Framing;
+
+/// Sends records to a TCP or UDP address.
+///
+/// Each write to the target is one record, which is how the logger writes to it.
+///
+/// See the [module documentation][self] for more details.
+#[derive(Debug)]
+pub struct NetworkTarget {
+    framing: Framing,
+    shared: Arc<Shared>,
+    /// Whether the thread that sends records has been started.
+    sending: bool,
+}
+
+/// The state shared with the thread that sends records.
+#[derive(Debug)]
+struct Shared {
+    state: Mutex<State>,
+    /// Wakes the thread up when a record is queued or the target is dropped.
+    wake: Condvar,
+}
+
+#[derive(Debug)]
+struct State {
+    settings: Settings,
+    capacity: usize,
+    queue: VecDeque<Vec<u8>>,
+    dropped: bool,
+}
+
+#[derive(Clone, Debug)]
+struct Settings {
+    protocol: Protocol,
+    addrs: Vec<SocketAddr>,
+    min_backoff: Duration,
+    max_backoff: Duration,
+    timeout: Duration,
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Protocol {
+    Tcp,
+    Udp,
+}
+
+#[derive(Debug)]
+enum Conn {
+    Tcp(TcpStream),
+    Udp(UdpSocket),
+}
+
+impl NetworkTarget {
+    /// Sends records over a TCP connection to `addr`.
+    ///
+    /// The address is resolved now, and each of its addresses is tried in turn when
+    /// connecting.
+    pub fn tcp(addr: impl ToSocketAddrs) -> io::Result<Self> {
+        Self::new(Protocol::Tcp, addr)
+    }
+
+    /// Sends records as UDP datagrams to `addr`, one record per datagram.
+    pub fn udp(addr: impl ToSocketAddrs) -> io::Result<Self> {
+        Self::new(Protocol::Udp, addr)
+    }
+
+    fn new(protocol: Protocol, addr: impl ToSocketAddrs) -> io::Result<Self> {
+        let addrs: Vec<_> = addr.to_socket_addrs()?.collect();
+        if addrs.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "no addresses to connect to",
+            ));
+        }
+
+        let state = State {
+            settings: Settings {
+                protocol,
+                addrs,
+                min_backoff: Duration::from_millis(100),
+                max_backoff: Duration::from_secs(30),
+                timeout: Duration::from_secs(1),
+            },
+            capacity: 1024,
+            queue: VecDeque::new(),
+            dropped: false,
+        };
+        Ok(Self {
+            framing: Framing::default(),
+            shared: Arc::new(Shared {
+                state: Mutex::new(state),
+                wake: Condvar::new(),
+            }),
+            sending: false,
+        })
+    }
+
+    /// Sets how records are delimited.
+    ///
+    /// The default is [`Framing::Newline`].
+    pub fn framing(&mut self, framing: Framing) -> &mut Self {
+        self.framing = framing;
+        self
+    }
+
+    /// Sets the number of records to queue while they can't be sent, at least one.
+    ///
+    /// The default is 1024 records.
+    pub fn buffer_capacity(&mut self, capacity: usize) -> &mut Self {
+        self.state().capacity = capacity.max(1);
+        self
+    }
+
+    /// Sets the delay before the first reconnection attempt, and the limit it doubles up to.
+    ///
+    /// The default is to start at 100 milliseconds, up to 30 seconds.
+    pub fn backoff(&mut self, min: Duration, max: Duration) -> &mut Self {
+        let mut state = self.state();
+        state.settings.min_backoff = min;
+        state.settings.max_backoff = max.max(min);
+        drop(state);
+        self
+    }
+
+    /// Sets how long connecting, and sending a record, may take before the connection is
+    /// given up on.
+    ///
+    /// The default is 1 second.
+    pub fn timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.state().settings.timeout = timeout;
+        self
+    }
+
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.shared.lock()
+    }
+
+    /// Starts the thread that sends records, if it isn't running yet.
+    fn start_sending(&mut self) {
+        if self.sending {
+            return;
+        }
+
+        let shared = self.shared.clone();
+        // Records are kept queued if the thread can't be spawned, and it's tried again
+        // on the next write
+        self.sending = thread::Builder::new()
+            .name("env_logger-net".to_owned())
+            .spawn(move || shared.send_loop())
+            .is_ok();
+    }
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Sends queued records, connecting whenever needed, until the target is dropped.
+    ///
+    /// The lock is only held to take records from the queue, so connecting and sending
+    /// never hold up logging threads. Records stay queued until there is a connection, so
+    /// the oldest ones are dropped first while disconnected.
+    fn send_loop(&self) {
+        let settings = self.lock().settings.clone();
+        let mut conn = None;
+        let mut backoff = settings.min_backoff;
+        let mut next_attempt = Instant::now();
+
+        loop {
+            let Some(connected) = &mut conn else {
+                if !self.wait_for_records() || !self.wait_until(next_attempt) {
+                    return;
+                }
+                // Wait between attempts even if connecting succeeds, in case sending fails
+                // right away
+                next_attempt = Instant::now() + backoff;
+                conn = settings
+                    .addrs
+                    .iter()
+                    .find_map(|addr| connect_to(settings.protocol, addr, settings.timeout).ok());
+                if conn.is_none() {
+                    backoff = (backoff * 2).min(settings.max_backoff);
+                }
+                continue;
+            };
+
+            let Some(record) = self.next_record() else {
+                return;
+            };
+            let sent = match connected {
+                Conn::Tcp(stream) => stream.write_all(&record),
+                Conn::Udp(socket) => socket.send(&record).map(|_| ()),
+            };
+            match sent {
+                Ok(()) => backoff = settings.min_backoff,
+                Err(_) => {
+                    // Part of the record may have been sent, so the connection is closed
+                    // and the whole record is sent again on the next one
+                    conn = None;
+                    self.lock().requeue(record);
+                }
+            }
+        }
+    }
+
+    /// Waits for a record to be queued, returning `false` once the target is dropped.
+    fn wait_for_records(&self) -> bool {
+        let mut state = self.lock();
+        loop {
+            if state.dropped {
+                return false;
+            }
+            if !state.queue.is_empty() {
+                return true;
+            }
+            state = self
+                .wake
+                .wait(state)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+    }
+
+    /// Waits for the next queued record to send, or returns `None` once the target is
+    /// dropped and the queue is empty.
+    fn next_record(&self) -> Option<Vec<u8>> {
+        let mut state = self.lock();
+        loop {
+            if let Some(record) = state.queue.pop_front() {
+                return Some(record);
+            }
+            if state.dropped {
+                return None;
+            }
+            state = self
+                .wake
+                .wait(state)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+    }
+
+    /// Waits until `deadline`, returning `false` early if the target is dropped.
+    fn wait_until(&self, deadline: Instant) -> bool {
+        let mut state = self.lock();
+        loop {
+            if state.dropped {
+                return false;
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                return true;
+            }
+            state = self
+                .wake
+                .wait_timeout(state, deadline - now)
+                .unwrap_or_else(PoisonError::into_inner)
+                .0;
+        }
+    }
+}
+
+impl State {
+    /// Queues a record, dropping the oldest ones to make room.
+    fn push(&mut self, record: Vec<u8>) {
+        while self.queue.len() >= self.capacity {
+            self.queue.pop_front();
+        }
+        self.queue.push_back(record);
+    }
+
+    /// Puts back a record that couldn't be sent, unless the queue filled up meanwhile and
+    /// it's the one to drop.
+    fn requeue(&mut self, record: Vec<u8>) {
+        if self.queue.len() < self.capacity {
+            self.queue.push_front(record);
+        }
+    }
+}
+
+fn connect_to(protocol: Protocol, addr: &SocketAddr, timeout: Duration) -> io::Result<Conn> {
+    match protocol {
+        Protocol::Tcp => {
+            let stream = TcpStream::connect_timeout(addr, timeout)?;
+            stream.set_write_timeout(Some(timeout))?;
+            Ok(Conn::Tcp(stream))
+        }
+        Protocol::Udp => {
+            let local: SocketAddr = if addr.is_ipv4() {
+                ([0, 0, 0, 0], 0).into()
+            } else {
+                ([0u16; 8], 0).into()
+            };
+            let socket = UdpSocket::bind(local)?;
+            socket.connect(addr)?;
+            Ok(Conn::Udp(socket))
+        }
+    }
+}
+
+impl io::Write for NetworkTarget {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let record = self.framing.frame(buf)?;
+
+        self.state().push(record);
+        self.start_sending();
+        self.shared.wake.notify_one();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Drop for NetworkTarget {
+    fn drop(&mut self) {
+        // The thread sends what's left in the queue if it's connected, and stops otherwise,
+        // without waiting for it
+        self.state().dropped = true;
+        self.shared.wake.notify_one();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+    use std::io::Write as _;
+    use std::net::{TcpListener, UdpSocket};
+    use std::time::{Duration, Instant};
+
+    use super::{Framing, NetworkTarget, State};
+
+    #[test]
+    fn frame_records() {
+        let mut target = NetworkTarget::udp("127.0.0.1:514").unwrap();
+        assert_eq!(target.framing.frame(b"first\n").unwrap(), b"first\n");
+        assert_eq!(target.framing.frame(b"second").unwrap(), b"second\n");
+
+        target.framing(Framing::OctetCounting);
+        assert_eq!(target.framing.frame(b"first\n").unwrap(), b"5 first");
+    }
+
+    #[test]
+    fn queue_drops_oldest_records() {
+        let target = NetworkTarget::udp("127.0.0.1:9").unwrap();
+        let mut state = State {
+            settings: target.state().settings.clone(),
+            capacity: 2,
+            queue: VecDeque::new(),
+            dropped: false,
+        };
+
+        for record in ["first", "second", "third"] {
+            state.push(record.as_bytes().to_vec());
+        }
+        assert_eq!(state.queue, [&b"second"[..], b"third"]);
+    }
+
+    #[test]
+    fn send_udp() {
+        let server = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let mut target = NetworkTarget::udp(server.local_addr().unwrap()).unwrap();
+
+        target.write_all(b"first\n").unwrap();
+        target.write_all(b"second\n").unwrap();
+
+        let mut buf = [0; 64];
+        let len = server.recv(&mut buf).unwrap();
+        assert_eq!(&buf[..len], b"first\n");
+        let len = server.recv(&mut buf).unwrap();
+        assert_eq!(&buf[..len], b"second\n");
+    }
+
+    #[test]
+    fn write_without_waiting_for_stalled_relay() {
+        // Connections are accepted by the system, but nothing is ever read
+        let server = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut target = NetworkTarget::tcp(server.local_addr().unwrap()).unwrap();
+        target.timeout(Duration::from_secs(5));
+
+        // Far more than the socket buffers hold
+        let record = vec![b'x'; 64 * 1024];
+        let start = Instant::now();
+        for _ in 0..512 {
+            target.write_all(&record).unwrap();
+        }
+        assert!(
+            start.elapsed() < Duration::from_secs(2),
+            "{:?}",
+            start.elapsed()
+        );
+    }
+}
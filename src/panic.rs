@@ -0,0 +1,68 @@
+//! The panic hook of the global logger.
+
+use std::any::Any;
+use std::backtrace::Backtrace;
+use std::panic::Location;
+use std::sync::Arc;
+
+use log::{Level, Log as _, Record};
+
+use crate::Logger;
+
+/// What the panic hook does, besides writing out the flight recorder.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Hook {
+    pub(crate) log: bool,
+    pub(crate) backtrace: bool,
+}
+
+impl Hook {
+    /// Sets the panic hook, calling the previous one afterwards.
+    pub(crate) fn install(self, logger: Arc<Logger>) {
+        let previous = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            // Writing the panic would panic again if printing the record panicked
+            if !crate::writer::is_printing() {
+                logger.dump_recorder();
+                if self.log {
+                    self.log_panic(&logger, info.payload(), info.location());
+                }
+            }
+            previous(info);
+        }));
+    }
+
+    fn log_panic(self, logger: &Logger, payload: &dyn Any, location: Option<&Location<'_>>) {
+        let thread = std::thread::current();
+        let thread = thread.name().unwrap_or("<unnamed>");
+        let payload = payload
+            .downcast_ref::<&str>()
+            .copied()
+            .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+            .unwrap_or("Box<dyn Any>");
+
+        let mut message = format!("thread '{thread}' panicked");
+        if let Some(location) = location {
+            message.push_str(&format!(" at {location}"));
+        }
+        message.push_str(&format!(":\n{payload}"));
+        if self.backtrace {
+            message.push_str(&format!(
+                "\nstack backtrace:\n{}",
+                Backtrace::force_capture()
+            ));
+        }
+
+        // Panics are written whatever the filter, like those of the default hook
+        logger.print(
+            &Record::builder()
+                .level(Level::Error)
+                .target("panic")
+                .file(location.map(|location| location.file()))
+                .line(location.map(|location| location.line()))
+                .args(format_args!("{message}"))
+                .build(),
+        );
+        logger.flush();
+    }
+}
@@ -0,0 +1,87 @@
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, PoisonError};
+
+use log::{Level, LevelFilter};
+
+use crate::writer::{OwnedRecordMeta, RecordMeta, Writer};
+
+/// The last records the filter left out, kept to be written when something goes wrong.
+#[derive(Debug)]
+pub(crate) struct FlightRecorder {
+    capacity: usize,
+    level: LevelFilter,
+    trigger: Level,
+    records: Mutex<VecDeque<(OwnedRecordMeta, Vec<u8>)>>,
+    dumping: AtomicBool,
+}
+
+impl FlightRecorder {
+    pub(crate) fn new(capacity: usize, level: LevelFilter, trigger: Level) -> Self {
+        Self {
+            capacity,
+            level,
+            trigger,
+            records: Mutex::new(VecDeque::with_capacity(capacity)),
+            dumping: AtomicBool::new(false),
+        }
+    }
+
+    pub(crate) fn level(&self) -> LevelFilter {
+        self.level
+    }
+
+    /// Whether records at `level` are kept when the filter leaves them out.
+    pub(crate) fn records(&self, level: Level) -> bool {
+        level <= self.level
+    }
+
+    /// Whether records at `level` write out the kept records.
+    pub(crate) fn triggers(&self, level: Level) -> bool {
+        level <= self.trigger
+    }
+
+    /// Keeps a formatted record, dropping the oldest one if full.
+    pub(crate) fn push(&self, meta: &RecordMeta<'_>, record: &[u8]) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        let mut records = self.records.lock().unwrap_or_else(PoisonError::into_inner);
+        let mut buf = if records.len() == self.capacity {
+            records.pop_front().map(|(_, buf)| buf).unwrap_or_default()
+        } else {
+            Vec::new()
+        };
+        buf.clear();
+        buf.extend_from_slice(record);
+        records.push_back((OwnedRecordMeta::new(meta), buf));
+    }
+
+    /// Writes the kept records to `writer`, oldest first, and forgets them.
+    ///
+    /// Nothing is written if the records are already being written, either by another
+    /// thread or by this one when a panic interrupted it.
+    pub(crate) fn dump(&self, writer: &Writer) {
+        struct Dumping<'a>(&'a AtomicBool);
+
+        impl Drop for Dumping<'_> {
+            fn drop(&mut self) {
+                self.0.store(false, Ordering::Release);
+            }
+        }
+
+        if self.dumping.swap(true, Ordering::Acquire) {
+            return;
+        }
+        let _dumping = Dumping(&self.dumping);
+        let mut records = self.records.lock().unwrap_or_else(PoisonError::into_inner);
+
+        let mut buf = writer.buffer();
+        for (meta, record) in records.drain(..) {
+            buf.clear();
+            let _ = buf.write(&record);
+            let _ = writer.print(&buf, &meta.as_meta());
+        }
+    }
+}
@@ -0,0 +1,238 @@
+use std::fmt::Write as _;
+use std::fs;
+use std::io::{self, BufRead as _, BufReader, Write as _};
+use std::os::unix::fs::PermissionsExt as _;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use crate::reload::FilterHandle;
+
+/// How long a client may stay idle before it is disconnected.
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(5);
+
+impl FilterHandle {
+    /// Serves a control endpoint for the filter on a Unix domain socket at `path`.
+    ///
+    /// # Protocol
+    ///
+    /// Clients send one request per line. Each response is zero or more lines of output,
+    /// followed by a line with `ok`, or with `error: ` and a message.
+    ///
+    /// - `get`: the current filter, in the same form as the `RUST_LOG` environment variable.
+    /// - `set <spec>`: replaces the filter with `spec`, in the same form as `RUST_LOG`.
+    ///   The current filter is kept if `spec` can't be parsed.
+    /// - `reset`: restores the filter the logger had when the socket was started.
+    /// - `targets`: the directives of the current filter, one per line.
+    ///
+    /// ```text
+    /// $ echo 'set info,db=trace' | nc -U /run/myapp/log.sock
+    /// ok
+    /// ```
+    ///
+    /// Connections are accepted on a background thread, and each one is served on a thread
+    /// of its own, so a slow client doesn't hold up the others. Each change is logged, along
+    /// with the old and new filters.
+    ///
+    /// # Permissions
+    ///
+    /// Anyone who can connect to the socket can change the filter, so its file is made
+    /// readable and writable only by the user running the process, with mode `0600`. The
+    /// mode is set right after the socket is bound, so `path` should be in a directory
+    /// that only trusted users can reach, like `/run/myapp`.
+    ///
+    /// The socket is served until the returned [`AdminSocket`] is dropped or
+    /// [detached][AdminSocket::detach], and its file is removed when it's dropped. A stale
+    /// socket file left at `path` by an earlier process is replaced.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the socket can't be bound, like if another process is serving it, or if
+    /// its permissions can't be set.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// let mut builder = env_logger::Builder::from_default_env();
+    /// let handle = builder.filter_handle();
+    /// builder.init();
+    ///
+    /// handle.serve_admin("/run/myapp/log.sock").unwrap().detach();
+    /// ```
+    pub fn serve_admin(&self, path: impl Into<PathBuf>) -> io::Result<AdminSocket> {
+        let path = path.into();
+        let listener = bind(&path)?;
+
+        if let Err(err) = fs::set_permissions(&path, fs::Permissions::from_mode(0o600)) {
+            let _ = fs::remove_file(&path);
+            return Err(err);
+        }
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let admin = Arc::new(Admin {
+            handle: self.clone(),
+            initial: self.get(),
+            stop: stop.clone(),
+        });
+        let thread = thread::Builder::new()
+            .name("env_logger-admin".to_owned())
+            .spawn({
+                let stop = stop.clone();
+                move || {
+                    for stream in listener.incoming() {
+                        if stop.load(Ordering::Acquire) {
+                            break;
+                        }
+                        if let Ok(stream) = stream {
+                            let admin = admin.clone();
+                            // Clients are dropped if their thread can't be spawned
+                            let _ = thread::Builder::new()
+                                .name("env_logger-admin-client".to_owned())
+                                .spawn(move || admin.serve(stream));
+                        }
+                    }
+                }
+            });
+        let thread = match thread {
+            Ok(thread) => thread,
+            Err(err) => {
+                let _ = fs::remove_file(&path);
+                return Err(err);
+            }
+        };
+
+        Ok(AdminSocket {
+            path,
+            stop,
+            thread: Some(thread),
+        })
+    }
+}
+
+fn bind(path: &Path) -> io::Result<UnixListener> {
+    match UnixListener::bind(path) {
+        Err(err) if err.kind() == io::ErrorKind::AddrInUse => {
+            // Only replace the socket if nothing is listening on it anymore
+            if UnixStream::connect(path).is_ok() {
+                return Err(err);
+            }
+            fs::remove_file(path)?;
+            UnixListener::bind(path)
+        }
+        result => result,
+    }
+}
+
+/// A background thread serving the [admin protocol][FilterHandle::serve_admin] on a
+/// Unix domain socket.
+///
+/// Created by [`FilterHandle::serve_admin`]. Dropping it stops accepting connections and
+/// removes the socket. Clients that are still connected are disconnected on their next
+/// request, or once they have been idle for a few seconds.
+#[must_use = "the socket is only served until the `AdminSocket` is dropped"]
+#[derive(Debug)]
+pub struct AdminSocket {
+    path: PathBuf,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl AdminSocket {
+    /// The path of the socket.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Keeps serving the socket for the rest of the process.
+    pub fn detach(mut self) {
+        self.thread = None;
+    }
+}
+
+impl Drop for AdminSocket {
+    fn drop(&mut self) {
+        if let Some(thread) = self.thread.take() {
+            self.stop.store(true, Ordering::Release);
+            // Wake the thread up from `accept`
+            if UnixStream::connect(&self.path).is_ok() {
+                let _ = thread.join();
+            }
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+}
+
+struct Admin {
+    handle: FilterHandle,
+    initial: Arc<env_filter::Filter>,
+    stop: Arc<AtomicBool>,
+}
+
+impl Admin {
+    fn serve(&self, stream: UnixStream) -> io::Result<()> {
+        stream.set_read_timeout(Some(CLIENT_TIMEOUT))?;
+        stream.set_write_timeout(Some(CLIENT_TIMEOUT))?;
+
+        let mut writer = &stream;
+        for line in BufReader::new(&stream).lines() {
+            let line = line?;
+            if self.stop.load(Ordering::Acquire) {
+                break;
+            }
+            let response = match self.respond(line.trim()) {
+                Ok(output) => format!("{output}ok\n"),
+                Err(err) => format!("error: {err}\n"),
+            };
+            writer.write_all(response.as_bytes())?;
+        }
+        Ok(())
+    }
+
+    fn respond(&self, request: &str) -> Result<String, String> {
+        let (command, argument) = match request.split_once(char::is_whitespace) {
+            Some((command, argument)) => (command, argument.trim()),
+            None => (request, ""),
+        };
+
+        match (command, argument) {
+            ("get", "") => Ok(format!("{}\n", self.handle.get())),
+            ("set", "") => Err("`set` needs a filter".to_owned()),
+            ("set", spec) => {
+                let filter = env_filter::Builder::new()
+                    .try_parse(spec)
+                    .map_err(|err| err.to_string())?
+                    .build();
+                self.set(filter);
+                Ok(String::new())
+            }
+            ("reset", "") => {
+                self.set((*self.initial).clone());
+                Ok(String::new())
+            }
+            ("targets", "") => {
+                let mut output = String::new();
+                for (module, level) in self.handle.get().directives() {
+                    let level = level.as_str().to_ascii_lowercase();
+                    let _ = match module {
+                        Some(module) => writeln!(output, "{module}={level}"),
+                        None => writeln!(output, "{level}"),
+                    };
+                }
+                Ok(output)
+            }
+            ("get" | "reset" | "targets", _) => Err(format!("`{command}` takes no arguments")),
+            _ => Err(format!("unknown command `{command}`")),
+        }
+    }
+
+    fn set(&self, filter: env_filter::Filter) {
+        let new = filter.to_string();
+        let old = self.handle.set(filter).to_string();
+        if old != new {
+            log::info!("log filter set by admin socket: `{old}` -> `{new}`");
+        }
+    }
+}
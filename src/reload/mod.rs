@@ -0,0 +1,564 @@
+//! Changing the filter of a running logger.
+//!
+//! A [`FilterHandle`] controls the filter of the [`Logger`] built by the builder it was
+//! taken from, so verbosity can be turned up or down without restarting the process.
+//!
+//! Only loggers built with a handle share their filter with it. Other loggers check a
+//! filter that never changes, without any synchronization.
+//!
+//! # Examples
+//!
+//! ```
+//! use env_logger::Builder;
+//!
+//! let mut builder = Builder::from_default_env();
+//! let handle = builder.filter_handle();
+//! builder.init();
+//!
+//! // Later, maybe in response to an admin request
+//! handle.parse("info,hyper=debug").unwrap();
+//! ```
+//!
+//! [`Logger`]: crate::Logger
+
+#[cfg(all(unix, feature = "admin"))]
+mod admin;
+#[cfg(all(unix, feature = "signal"))]
+mod signal;
+mod watch;
+
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock, PoisonError, RwLock};
+use std::time::{Duration, Instant};
+
+use log::LevelFilter;
+
+#[cfg(all(unix, feature = "admin"))]
+pub use admin::AdminSocket;
+#[cfg(all(unix, feature = "signal"))]
+pub use signal::SignalWatcher;
+pub use watch::FileWatcher;
+
+/// A handle to the filter of a [`Logger`][crate::Logger].
+///
+/// Handles are cheap to clone, and every clone controls the same logger.
+/// See the [module documentation][self] for more details.
+#[derive(Clone, Debug)]
+pub struct FilterHandle {
+    shared: Arc<SharedFilter>,
+}
+
+impl FilterHandle {
+    pub(crate) fn new(shared: Arc<SharedFilter>) -> Self {
+        Self { shared }
+    }
+
+    /// The current filter, including any [override][FilterHandle::override_for].
+    pub fn get(&self) -> Arc<env_filter::Filter> {
+        self.shared.get()
+    }
+
+    /// Replaces the filter, returning the previous one.
+    ///
+    /// Neither filter includes an [override][FilterHandle::override_for], which is kept
+    /// on top of the new filter.
+    ///
+    /// If the logger is the global logger, the [maximum level][log::max_level] is updated
+    /// to match the new filter.
+    pub fn set(&self, filter: env_filter::Filter) -> Arc<env_filter::Filter> {
+        self.shared.set(filter)
+    }
+
+    /// Replaces the filter with one parsed in the same form as the `RUST_LOG`
+    /// environment variable.
+    ///
+    /// The current filter is kept if `spec` can't be parsed.
+    pub fn parse(&self, spec: &str) -> Result<Arc<env_filter::Filter>, env_filter::ParseError> {
+        let filter = env_filter::Builder::new().try_parse(spec)?.build();
+        Ok(self.set(filter))
+    }
+
+    /// Raises the level of the directive without a module by one step, like from `info`
+    /// to `debug`, returning the new level.
+    ///
+    /// Directives for specific modules are unchanged.
+    pub fn raise_level(&self) -> LevelFilter {
+        self.step_level(LevelFilter::increment_severity)
+    }
+
+    /// Lowers the level of the directive without a module by one step, like from `info`
+    /// to `warn`, returning the new level.
+    ///
+    /// Directives for specific modules are unchanged.
+    pub fn lower_level(&self) -> LevelFilter {
+        self.step_level(LevelFilter::decrement_severity)
+    }
+
+    /// Layers an overriding filter over the current filter until `duration` has passed.
+    ///
+    /// `spec` is in the same form as the `RUST_LOG` environment variable. Its directives
+    /// replace the directives of the current filter for the same modules, and its message
+    /// filter replaces the current message filter, if it has one. Once the override expires,
+    /// the logger reverts to the filter it would have had without it.
+    ///
+    /// The override replaces any earlier override, and is kept on top of the filter when the
+    /// filter itself is changed, like by [`FilterHandle::set`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    ///
+    /// let mut builder = env_logger::Builder::new();
+    /// builder.parse_filters("info");
+    /// let handle = builder.filter_handle();
+    /// builder.init();
+    ///
+    /// // Trace the database for the next ten minutes
+    /// handle.override_for("db=trace", Duration::from_secs(10 * 60)).unwrap();
+    /// assert!(log::log_enabled!(target: "db::pool", log::Level::Trace));
+    /// ```
+    pub fn override_for(
+        &self,
+        spec: &str,
+        duration: Duration,
+    ) -> Result<(), env_filter::ParseError> {
+        env_filter::Builder::new().try_parse(spec)?;
+        self.shared.override_for(spec.to_owned(), duration);
+        log::info!("log filter overridden with `{spec}` for {duration:?}");
+        Ok(())
+    }
+
+    /// Removes the override set by [`FilterHandle::override_for`] before it expires.
+    pub fn clear_override(&self) {
+        self.shared.clear_override();
+    }
+
+    fn step_level(&self, step: fn(&LevelFilter) -> LevelFilter) -> LevelFilter {
+        let mut level = LevelFilter::Off;
+        self.shared.update(|filter| {
+            // Without a directive for all modules, other modules are off
+            let current = filter
+                .directives()
+                .find(|(module, _)| module.is_none())
+                .map(|(_, level)| level)
+                .unwrap_or(LevelFilter::Off);
+            level = step(&current);
+            filter.to_builder().filter_level(level).build()
+        });
+        level
+    }
+}
+
+/// The filter of the global logger, if it's an env logger.
+static GLOBAL: OnceLock<Global> = OnceLock::new();
+
+#[derive(Debug)]
+enum Global {
+    /// The maximum level of a filter that never changes, including the floor.
+    ///
+    /// The lock keeps concurrent refreshes from setting an outdated maximum level.
+    Fixed(Mutex<LevelFilter>),
+    Shared(Arc<SharedFilter>),
+}
+
+/// Updates [`log::max_level`] for the global logger, like when a boost starts or ends.
+pub(crate) fn refresh_max_level() {
+    match GLOBAL.get() {
+        Some(Global::Fixed(level)) => {
+            let level = level.lock().unwrap_or_else(PoisonError::into_inner);
+            log::set_max_level(level.max(crate::boost::max_level()));
+        }
+        Some(Global::Shared(shared)) => {
+            let state = shared.state.write().unwrap_or_else(PoisonError::into_inner);
+            log::set_max_level(max_level(&state));
+        }
+        None => {}
+    }
+}
+
+/// The maximum level of the global logger, including any [boosts][crate::boost].
+fn max_level(state: &State) -> LevelFilter {
+    state
+        .effective
+        .filter()
+        .max(state.floor)
+        .max(crate::boost::max_level())
+}
+
+/// The filter of a [`Logger`][crate::Logger].
+#[derive(Debug)]
+pub(crate) enum LoggerFilter {
+    /// A filter without a [`FilterHandle`], which is checked without any synchronization.
+    Fixed {
+        filter: env_filter::Filter,
+        /// The level the logger handles records up to regardless of the filter.
+        floor: LevelFilter,
+    },
+    /// A filter shared with the [`FilterHandle`]s taken from the builder.
+    Shared(Arc<SharedFilter>),
+}
+
+impl LoggerFilter {
+    /// Uses `shared` for the filter if the builder handed out a handle to it.
+    pub(crate) fn new(filter: env_filter::Filter, shared: Option<Arc<SharedFilter>>) -> Self {
+        match shared {
+            Some(shared) => {
+                shared.set(filter);
+                Self::Shared(shared)
+            }
+            None => Self::Fixed {
+                filter,
+                floor: LevelFilter::Off,
+            },
+        }
+    }
+
+    /// Calls `f` with the current filter, including any override.
+    pub(crate) fn with<R>(&self, f: impl FnOnce(&env_filter::Filter) -> R) -> R {
+        match self {
+            Self::Fixed { filter, .. } => f(filter),
+            Self::Shared(shared) => f(&shared.get()),
+        }
+    }
+
+    /// Keeps [`log::max_level`] at or above `level`, for records the logger handles even
+    /// though the filter leaves them out.
+    pub(crate) fn set_floor(&mut self, level: LevelFilter) {
+        match self {
+            Self::Fixed { floor, .. } => *floor = level,
+            Self::Shared(shared) => shared.set_floor(level),
+        }
+    }
+
+    /// Marks the logger as the global logger, whose filter sets [`log::max_level`].
+    pub(crate) fn set_global(&self) {
+        match self {
+            Self::Fixed { filter, floor } => {
+                let level = filter.filter().max(*floor);
+                if GLOBAL.set(Global::Fixed(Mutex::new(level))).is_ok() {
+                    refresh_max_level();
+                }
+            }
+            Self::Shared(shared) => shared.set_global(),
+        }
+    }
+}
+
+/// The filter of a logger, shared with its [`FilterHandle`]s.
+#[derive(Debug)]
+pub(crate) struct SharedFilter {
+    state: RwLock<State>,
+    /// When the override expires, in nanoseconds since `epoch`, or `NO_OVERRIDE`.
+    ///
+    /// This is checked on every call to `get`, so an expired override is removed without
+    /// taking the lock for writing until it has actually expired.
+    expires: AtomicU64,
+    epoch: Instant,
+    global: AtomicBool,
+    /// How far tests have moved the clock forward, in nanoseconds.
+    #[cfg(test)]
+    skew: AtomicU64,
+}
+
+const NO_OVERRIDE: u64 = u64::MAX;
+
+#[derive(Debug)]
+struct State {
+    base: Arc<env_filter::Filter>,
+    overlay: Option<String>,
+    // The filter is behind its own `Arc` so readers hold the lock only long enough to clone it,
+    // never while formatting or writing a record
+    effective: Arc<env_filter::Filter>,
+    /// The level the logger handles records up to regardless of the filter.
+    floor: LevelFilter,
+}
+
+impl SharedFilter {
+    pub(crate) fn new(filter: env_filter::Filter) -> Self {
+        let filter = Arc::new(filter);
+        Self {
+            state: RwLock::new(State {
+                base: filter.clone(),
+                overlay: None,
+                effective: filter,
+                floor: LevelFilter::Off,
+            }),
+            expires: AtomicU64::new(NO_OVERRIDE),
+            epoch: Instant::now(),
+            global: AtomicBool::new(false),
+            #[cfg(test)]
+            skew: AtomicU64::new(0),
+        }
+    }
+
+    /// The current filter, including any override.
+    pub(crate) fn get(&self) -> Arc<env_filter::Filter> {
+        if self.expires.load(Ordering::Relaxed) != NO_OVERRIDE && self.override_expired() {
+            self.write(|state| state.overlay = None);
+        }
+
+        self.state
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .effective
+            .clone()
+    }
+
+    pub(crate) fn set(&self, filter: env_filter::Filter) -> Arc<env_filter::Filter> {
+        self.update(|_| filter)
+    }
+
+    /// Replaces the filter with one derived from the current filter, returning the previous one.
+    ///
+    /// Both filters are without any override, which is kept on top of the new filter.
+    /// The filter can't be changed by anything else in the meantime.
+    pub(crate) fn update(
+        &self,
+        f: impl FnOnce(&env_filter::Filter) -> env_filter::Filter,
+    ) -> Arc<env_filter::Filter> {
+        self.write(|state| {
+            let filter = Arc::new(f(&state.base));
+            std::mem::replace(&mut state.base, filter)
+        })
+    }
+
+    /// Layers the directives of `spec` over the filter until `duration` has passed.
+    pub(crate) fn override_for(&self, spec: String, duration: Duration) {
+        // Saturate, an override that expires in centuries might as well not expire
+        let expires = self
+            .elapsed()
+            .checked_add(duration)
+            .and_then(|expires| u64::try_from(expires.as_nanos()).ok())
+            .unwrap_or(NO_OVERRIDE - 1);
+        self.write(|state| {
+            state.overlay = Some(spec);
+            self.expires.store(expires, Ordering::Relaxed);
+        });
+    }
+
+    pub(crate) fn clear_override(&self) {
+        self.write(|state| state.overlay = None);
+    }
+
+    fn set_floor(&self, level: LevelFilter) {
+        self.write(|state| state.floor = level);
+    }
+
+    /// Marks the logger as the global logger, whose filter sets [`log::max_level`].
+    fn set_global(self: &Arc<Self>) {
+        let state = self.state.write().unwrap_or_else(PoisonError::into_inner);
+        self.global.store(true, Ordering::Release);
+        let _ = GLOBAL.set(Global::Shared(self.clone()));
+        log::set_max_level(max_level(&state));
+    }
+
+    fn override_expired(&self) -> bool {
+        let now = self.elapsed().as_nanos();
+        u128::from(self.expires.load(Ordering::Relaxed)) <= now
+    }
+
+    fn elapsed(&self) -> Duration {
+        let elapsed = self.epoch.elapsed();
+        #[cfg(test)]
+        let elapsed = elapsed + Duration::from_nanos(self.skew.load(Ordering::Relaxed));
+        elapsed
+    }
+
+    /// Moves the clock forward by `duration`, as if that much time had passed.
+    #[cfg(test)]
+    fn advance(&self, duration: Duration) {
+        let duration = u64::try_from(duration.as_nanos()).unwrap();
+        self.skew.fetch_add(duration, Ordering::Relaxed);
+    }
+
+    /// Changes the state and recomputes the effective filter.
+    fn write<R>(&self, f: impl FnOnce(&mut State) -> R) -> R {
+        let mut state = self.state.write().unwrap_or_else(PoisonError::into_inner);
+        if state.overlay.is_some() && self.override_expired() {
+            state.overlay = None;
+        }
+
+        let result = f(&mut state);
+
+        state.effective = match &state.overlay {
+            Some(spec) => Arc::new(layer(&state.base, spec)),
+            None => {
+                self.expires.store(NO_OVERRIDE, Ordering::Relaxed);
+                state.base.clone()
+            }
+        };
+
+        // Update the maximum level while the lock is held, so concurrent updates can't
+        // leave it out of step with the filter
+        if self.global.load(Ordering::Acquire) {
+            log::set_max_level(max_level(&state));
+        }
+        result
+    }
+}
+
+/// Adds the directives and message filter of `spec` to `base`.
+///
+/// `spec` must have been parsed successfully before.
+fn layer(base: &env_filter::Filter, spec: &str) -> env_filter::Filter {
+    let (directives, message_filter) = match spec.split_once('/') {
+        Some((directives, message_filter)) => (directives, Some(message_filter)),
+        None => (spec, None),
+    };
+
+    // Parse the directives on their own, so `base`'s message filter is kept
+    // if `spec` doesn't have one
+    let mut overlay = env_filter::Builder::new();
+    let _ = overlay.try_parse(directives);
+
+    let mut builder = base.to_builder();
+    for (module, level) in overlay.directives() {
+        builder.filter(module, level);
+    }
+    if let Some(message_filter) = message_filter {
+        let _ = builder.try_parse(&format!("/{message_filter}"));
+    }
+    builder.build()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use log::{Level, LevelFilter, Log as _, Metadata};
+
+    use crate::Builder;
+
+    fn enabled(logger: &crate::Logger, level: Level, target: &str) -> bool {
+        logger.enabled(&Metadata::builder().level(level).target(target).build())
+    }
+
+    #[test]
+    fn handle_changes_built_logger() {
+        let mut builder = Builder::new();
+        builder.parse_filters("warn");
+        let handle = builder.filter_handle();
+        let logger = builder.build();
+
+        assert_eq!(handle.get().to_string(), "warn");
+        assert!(!enabled(&logger, Level::Info, "app"));
+
+        let previous = handle.parse("info,hyper=off").unwrap();
+        assert_eq!(previous.to_string(), "warn");
+        assert_eq!(logger.filter(), LevelFilter::Info);
+        assert!(enabled(&logger, Level::Info, "app"));
+        assert!(!enabled(&logger, Level::Error, "hyper::client"));
+    }
+
+    #[test]
+    fn handle_steps_global_level() {
+        let mut builder = Builder::new();
+        builder.parse_filters("info,hyper=warn");
+        let handle = builder.filter_handle();
+        let logger = builder.build();
+
+        assert_eq!(handle.raise_level(), LevelFilter::Debug);
+        assert_eq!(handle.raise_level(), LevelFilter::Trace);
+        assert_eq!(handle.raise_level(), LevelFilter::Trace);
+        assert_eq!(handle.get().to_string(), "trace,hyper=warn");
+        assert!(enabled(&logger, Level::Trace, "app"));
+
+        for _ in 0..5 {
+            handle.lower_level();
+        }
+        assert_eq!(handle.get().to_string(), "off,hyper=warn");
+        assert_eq!(logger.filter(), LevelFilter::Warn);
+        assert!(!enabled(&logger, Level::Error, "app"));
+    }
+
+    #[test]
+    fn handle_steps_missing_global_level() {
+        let mut builder = Builder::new();
+        builder.parse_filters("hyper=warn");
+        let handle = builder.filter_handle();
+        builder.build();
+
+        assert_eq!(handle.raise_level(), LevelFilter::Error);
+        assert_eq!(handle.get().to_string(), "error,hyper=warn");
+    }
+
+    #[test]
+    fn override_layers_and_expires() {
+        let mut builder = Builder::new();
+        builder.parse_filters("info,db=warn/request");
+        let handle = builder.filter_handle();
+        let logger = builder.build();
+
+        handle
+            .override_for("db::pool=trace,http=debug", Duration::from_secs(60))
+            .unwrap();
+        assert_eq!(
+            handle.get().to_string(),
+            "info,db=warn,http=debug,db::pool=trace/request"
+        );
+        assert_eq!(logger.filter(), LevelFilter::Trace);
+
+        // The override stays on top of changes to the filter
+        assert_eq!(handle.raise_level(), LevelFilter::Debug);
+        assert_eq!(
+            handle.get().to_string(),
+            "debug,db=warn,http=debug,db::pool=trace/request"
+        );
+
+        handle.shared.advance(Duration::from_secs(59));
+        assert_eq!(
+            handle.get().to_string(),
+            "debug,db=warn,http=debug,db::pool=trace/request"
+        );
+
+        handle.shared.advance(Duration::from_secs(1));
+        assert_eq!(handle.get().to_string(), "debug,db=warn/request");
+        assert_eq!(logger.filter(), LevelFilter::Debug);
+    }
+
+    #[test]
+    fn override_replaces_message_filter() {
+        let mut builder = Builder::new();
+        builder.parse_filters("info/request");
+        let handle = builder.filter_handle();
+        builder.build();
+
+        handle
+            .override_for("warn/response", Duration::from_secs(60))
+            .unwrap();
+        assert_eq!(handle.get().to_string(), "warn/response");
+
+        handle
+            .override_for("db=debug", Duration::from_secs(60))
+            .unwrap();
+        assert_eq!(handle.get().to_string(), "info,db=debug/request");
+
+        handle.clear_override();
+        assert_eq!(handle.get().to_string(), "info/request");
+    }
+
+    #[test]
+    fn override_rejects_invalid_spec() {
+        let mut builder = Builder::new();
+        builder.parse_filters("info");
+        let handle = builder.filter_handle();
+        builder.build();
+
+        assert!(handle.override_for("db=loud", Duration::MAX).is_err());
+        assert!(handle.override_for("db=trace", Duration::MAX).is_ok());
+        assert_eq!(handle.get().to_string(), "info,db=trace");
+    }
+
+    #[test]
+    fn handle_keeps_filter_on_parse_error() {
+        let mut builder = Builder::new();
+        builder.filter_level(LevelFilter::Debug);
+        let handle = builder.filter_handle();
+        let logger = builder.build();
+
+        assert!(handle.parse("app=loud").is_err());
+        assert_eq!(handle.get().to_string(), "debug");
+        assert!(enabled(&logger, Level::Debug, "app"));
+    }
+}
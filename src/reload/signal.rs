@@ -0,0 +1,147 @@
+use std::io;
+use std::os::raw::c_int;
+use std::thread::{self, JoinHandle};
+
+use log::LevelFilter;
+use signal_hook::consts::{SIGUSR1, SIGUSR2};
+use signal_hook::iterator::{Handle, Signals};
+
+use crate::boost::BoostGuard;
+use crate::reload::FilterHandle;
+
+impl FilterHandle {
+    /// Raises the level one step on `SIGUSR1` and lowers it one step on `SIGUSR2`.
+    ///
+    /// Signals are handled on a background thread, as if by [`FilterHandle::raise_level`]
+    /// and [`FilterHandle::lower_level`]. Each change is logged as a warning, which is
+    /// [boosted][crate::boost] past the filter so it's written even once the level has
+    /// been lowered below `warn`.
+    ///
+    /// Signals are handled until the returned [`SignalWatcher`] is dropped or
+    /// [detached][SignalWatcher::detach].
+    ///
+    /// # Errors
+    ///
+    /// Fails if the signal handlers can't be registered.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// let mut builder = env_logger::Builder::from_default_env();
+    /// let handle = builder.filter_handle();
+    /// builder.init();
+    ///
+    /// // `kill -USR1 <pid>` now makes the process more verbose
+    /// handle.watch_signals().unwrap().detach();
+    /// ```
+    pub fn watch_signals(&self) -> io::Result<SignalWatcher> {
+        let handle = self.clone();
+        SignalWatcher::spawn(&[SIGUSR1, SIGUSR2], move |signal| {
+            // Announce the new level even if it leaves out warnings
+            let _boost = BoostGuard::new(LevelFilter::Warn);
+            if signal == SIGUSR1 {
+                let level = handle.raise_level();
+                log::warn!("log level raised to {level} by SIGUSR1");
+            } else {
+                let level = handle.lower_level();
+                log::warn!("log level lowered to {level} by SIGUSR2");
+            }
+        })
+    }
+}
+
+/// A background thread handling signals.
+///
+/// Created by [`FilterHandle::watch_signals`] or
+/// [`ReopenHandle::watch_signals`][crate::file::ReopenHandle::watch_signals]. Dropping
+/// the watcher stops it.
+#[must_use = "signals are only handled until the `SignalWatcher` is dropped"]
+#[derive(Debug)]
+pub struct SignalWatcher {
+    close: Handle,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl SignalWatcher {
+    /// Calls `on_signal` with each of `signals` received, on a background thread.
+    pub(crate) fn spawn(
+        signals: &[c_int],
+        mut on_signal: impl FnMut(c_int) + Send + 'static,
+    ) -> io::Result<Self> {
+        let mut signals = Signals::new(signals)?;
+        let close = signals.handle();
+
+        let thread = thread::Builder::new()
+            .name("env_logger-signals".to_owned())
+            .spawn(move || {
+                for signal in signals.forever() {
+                    on_signal(signal);
+                }
+            });
+        let thread = match thread {
+            Ok(thread) => thread,
+            Err(err) => {
+                close.close();
+                return Err(err);
+            }
+        };
+
+        Ok(Self {
+            close,
+            thread: Some(thread),
+        })
+    }
+
+    /// Keeps handling signals for the rest of the process.
+    pub fn detach(mut self) {
+        self.thread = None;
+    }
+}
+
+impl Drop for SignalWatcher {
+    fn drop(&mut self) {
+        if let Some(thread) = self.thread.take() {
+            self.close.close();
+            let _ = thread.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use signal_hook::consts::{SIGUSR1, SIGUSR2};
+    use signal_hook::low_level::raise;
+
+    use crate::reload::FilterHandle;
+    use crate::Builder;
+
+    fn raise_until(handle: &FilterHandle, signal: i32, expected: &str) {
+        raise(signal).unwrap();
+        let start = Instant::now();
+        while handle.get().to_string() != expected {
+            assert!(
+                start.elapsed() < Duration::from_secs(5),
+                "filter is `{}`, expected `{expected}`",
+                handle.get()
+            );
+            std::thread::sleep(Duration::from_millis(5));
+        }
+    }
+
+    #[test]
+    fn watch_signals_steps_level() {
+        let mut builder = Builder::new();
+        builder.parse_filters("info,hyper=warn");
+        let handle = builder.filter_handle();
+        builder.build();
+        let watcher = handle.watch_signals().unwrap();
+
+        raise_until(&handle, SIGUSR1, "debug,hyper=warn");
+        raise_until(&handle, SIGUSR2, "info,hyper=warn");
+        raise_until(&handle, SIGUSR2, "warn,hyper=warn");
+
+        drop(watcher);
+    }
+}
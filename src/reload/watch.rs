@@ -0,0 +1,298 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Condvar, Mutex, PoisonError};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, SystemTime};
+
+use crate::reload::FilterHandle;
+
+impl FilterHandle {
+    /// Watches a file containing a filter in the same form as the `RUST_LOG` environment
+    /// variable, replacing the filter whenever the file changes.
+    ///
+    /// The file is checked once before this method returns, then every `interval` on a
+    /// background thread, by polling its modification time and length. A change is only
+    /// read once the file has stayed the same for a whole interval, so a file that is still
+    /// being written isn't picked up half-way through. Each change is logged, along with
+    /// the old and new filters. If the file can't be read or parsed, a warning is logged
+    /// and the current filter is kept.
+    ///
+    /// The file is watched until the returned [`FileWatcher`] is dropped or
+    /// [detached][FileWatcher::detach].
+    ///
+    /// # Errors
+    ///
+    /// Fails if the background thread can't be spawned.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::time::Duration;
+    ///
+    /// let mut builder = env_logger::Builder::from_default_env();
+    /// let handle = builder.filter_handle();
+    /// builder.init();
+    ///
+    /// handle
+    ///     .watch_file("/etc/myapp/log-filter", Duration::from_secs(1))
+    ///     .unwrap()
+    ///     .detach();
+    /// ```
+    pub fn watch_file(
+        &self,
+        path: impl Into<PathBuf>,
+        interval: Duration,
+    ) -> io::Result<FileWatcher> {
+        let mut watch = Watch::new(self.clone(), path.into());
+
+        let stop = Arc::new(Stop::default());
+        let thread = thread::Builder::new()
+            .name("env_logger-watch".to_owned())
+            .spawn({
+                let stop = stop.clone();
+                move || {
+                    while !stop.wait(interval) {
+                        watch.poll();
+                    }
+                }
+            })?;
+
+        Ok(FileWatcher {
+            stop,
+            thread: Some(thread),
+        })
+    }
+}
+
+/// A background thread watching a filter file.
+///
+/// Created by [`FilterHandle::watch_file`]. Dropping the watcher stops it.
+#[must_use = "the file is only watched until the `FileWatcher` is dropped"]
+#[derive(Debug)]
+pub struct FileWatcher {
+    stop: Arc<Stop>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl FileWatcher {
+    /// Keeps watching the file for the rest of the process.
+    pub fn detach(mut self) {
+        self.thread = None;
+    }
+}
+
+impl Drop for FileWatcher {
+    fn drop(&mut self) {
+        if let Some(thread) = self.thread.take() {
+            self.stop.set();
+            let _ = thread.join();
+        }
+    }
+}
+
+/// A stop flag that wakes the watcher up early.
+#[derive(Debug, Default)]
+struct Stop {
+    stopped: Mutex<bool>,
+    changed: Condvar,
+}
+
+impl Stop {
+    fn set(&self) {
+        *self.stopped.lock().unwrap_or_else(PoisonError::into_inner) = true;
+        self.changed.notify_all();
+    }
+
+    /// Waits for `timeout`, returning early with `true` if the watcher was stopped.
+    fn wait(&self, timeout: Duration) -> bool {
+        let stopped = self.stopped.lock().unwrap_or_else(PoisonError::into_inner);
+        let (stopped, _) = self
+            .changed
+            .wait_timeout_while(stopped, timeout, |stopped| !*stopped)
+            .unwrap_or_else(PoisonError::into_inner);
+        *stopped
+    }
+}
+
+struct Watch {
+    handle: FilterHandle,
+    path: PathBuf,
+    /// The stamp of the file when it was last read.
+    stamp: Option<Stamp>,
+    /// The stamp of the file when it was last polled.
+    seen: Option<Stamp>,
+}
+
+/// The modification time and length of a file.
+type Stamp = (Option<SystemTime>, u64);
+
+impl Watch {
+    /// Starts watching `path`, reading it right away if it exists.
+    fn new(handle: FilterHandle, path: PathBuf) -> Self {
+        let mut watch = Self {
+            handle,
+            path,
+            stamp: None,
+            seen: None,
+        };
+        if let Some(stamp) = watch.stamp() {
+            watch.reload(Some(stamp));
+        }
+        watch
+    }
+
+    fn stamp(&self) -> Option<Stamp> {
+        let metadata = fs::metadata(&self.path).ok()?;
+        Some((metadata.modified().ok(), metadata.len()))
+    }
+
+    fn poll(&mut self) {
+        let stamp = self.stamp();
+        let settled = stamp == self.seen;
+        self.seen = stamp;
+        if settled && stamp != self.stamp {
+            self.reload(stamp);
+        }
+    }
+
+    fn reload(&mut self, stamp: Option<Stamp>) {
+        self.stamp = stamp;
+        self.seen = stamp;
+
+        match read_filter(&self.path) {
+            Ok(filter) => {
+                let new = filter.to_string();
+                let old = self.handle.set(filter).to_string();
+                if old != new {
+                    log::info!(
+                        "reloaded log filter from {}: `{old}` -> `{new}`",
+                        self.path.display()
+                    );
+                }
+            }
+            Err(err) => log::warn!(
+                "keeping log filter `{}`, failed to reload {}: {err}",
+                self.handle.get(),
+                self.path.display()
+            ),
+        }
+    }
+}
+
+fn read_filter(path: &Path) -> Result<env_filter::Filter, Box<dyn std::error::Error>> {
+    let spec = fs::read_to_string(path)?;
+    Ok(env_filter::Builder::new().try_parse(spec.trim())?.build())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::PathBuf;
+    use std::time::{Duration, Instant};
+
+    use log::{Level, Log as _, Metadata};
+
+    use super::Watch;
+    use crate::Builder;
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("env_logger-watch-{}-{name}", std::process::id()))
+    }
+
+    fn enabled(logger: &crate::Logger, level: Level, target: &str) -> bool {
+        logger.enabled(&Metadata::builder().level(level).target(target).build())
+    }
+
+    fn eventually(mut f: impl FnMut() -> bool) -> bool {
+        let start = Instant::now();
+        while start.elapsed() < Duration::from_secs(10) {
+            if f() {
+                return true;
+            }
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        false
+    }
+
+    #[test]
+    fn watch_file_reloads_on_change() {
+        let path = temp_path("reloads_on_change");
+        fs::write(&path, "info\n").unwrap();
+
+        let mut builder = Builder::new();
+        builder.parse_filters("warn");
+        let handle = builder.filter_handle();
+        let logger = builder.build();
+
+        let watcher = handle.watch_file(&path, Duration::from_millis(10)).unwrap();
+        // The file is read before `watch_file` returns
+        assert_eq!(handle.get().to_string(), "info");
+
+        fs::write(&path, "info,app=debug\n").unwrap();
+        assert!(eventually(|| enabled(&logger, Level::Debug, "app::db")));
+
+        fs::write(&path, "error").unwrap();
+        assert!(eventually(|| !enabled(&logger, Level::Info, "app")));
+
+        // Changes after the watcher is dropped are ignored, its thread has stopped
+        drop(watcher);
+        fs::write(&path, "trace").unwrap();
+        assert_eq!(handle.get().to_string(), "error");
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn poll_keeps_filter_on_errors() {
+        let path = temp_path("keeps_filter_on_errors");
+        fs::write(&path, "info\n").unwrap();
+
+        let handle = Builder::new().filter_handle();
+        let mut watch = Watch::new(handle.clone(), path.clone());
+        assert_eq!(handle.get().to_string(), "info");
+
+        // A change is read once it has been seen twice in a row
+        fs::write(&path, "info,app=debug\n").unwrap();
+        watch.poll();
+        assert_eq!(handle.get().to_string(), "info");
+        watch.poll();
+        assert_eq!(handle.get().to_string(), "info,app=debug");
+
+        // Invalid filters are ignored
+        fs::write(&path, "app=loud,other=debug\n").unwrap();
+        watch.poll();
+        watch.poll();
+        assert_eq!(watch.stamp, watch.stamp());
+        assert_eq!(handle.get().to_string(), "info,app=debug");
+
+        // So is removing the file
+        fs::remove_file(&path).unwrap();
+        watch.poll();
+        watch.poll();
+        assert_eq!(watch.stamp, None);
+        assert_eq!(handle.get().to_string(), "info,app=debug");
+
+        fs::write(&path, "error").unwrap();
+        watch.poll();
+        watch.poll();
+        assert_eq!(handle.get().to_string(), "error");
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn watch_missing_file() {
+        let path = temp_path("missing_file");
+        let _ = fs::remove_file(&path);
+
+        let handle = Builder::new().filter_handle();
+        let _watcher = handle.watch_file(&path, Duration::from_millis(10)).unwrap();
+        assert_eq!(handle.get().to_string(), "error");
+
+        fs::write(&path, "debug").unwrap();
+        assert!(eventually(|| handle.get().to_string() == "debug"));
+
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,497 @@
+//! Logging to syslog.
+//!
+//! [`Syslog`] formats records as syslog messages, in either the [RFC 5424] format or the
+//! legacy [RFC 3164] (BSD) format, and a [`SyslogSocket`] sends them to the local syslog
+//! daemon or to a remote one over UDP. [`Builder::syslog`] sets both up at once.
+//!
+//! Log levels map to syslog severities as follows:
+//!
+//! | Level   | Severity      |
+//! |---------|---------------|
+//! | `error` | 3 (`err`)     |
+//! | `warn`  | 4 (`warning`) |
+//! | `info`  | 6 (`info`)    |
+//! | `debug` | 7 (`debug`)   |
+//! | `trace` | 7 (`debug`)   |
+//!
+//! In the RFC 5424 format, the target of a record is sent as its MSGID. With the `kv`
+//! feature, its key-values are sent as [structured data].
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use env_logger::syslog::{Facility, Syslog, SyslogSocket};
+//!
+//! let mut syslog = Syslog::new();
+//! syslog.facility(Facility::Daemon).app_name("myapp");
+//!
+//! env_logger::Builder::from_default_env()
+//!     .syslog(syslog, SyslogSocket::unix("/dev/log")?)
+//!     .init();
+//! # Ok::<(), std::io::Error>(())
+//! ```
+//!
+//! [RFC 5424]: https://datatracker.ietf.org/doc/html/rfc5424
+//! [RFC 3164]: https://datatracker.ietf.org/doc/html/rfc3164
+//! [structured data]: https://datatracker.ietf.org/doc/html/rfc5424#section-6.3
+//! [`Builder::syslog`]: crate::Builder::syslog
+
+mod socket;
+mod time;
+
+use std::io;
+use std::time::SystemTime;
+
+use log::{Level, Record};
+
+use crate::fmt::Formatter;
+
+pub use socket::SyslogSocket;
+
+/// The syslog facility of the messages, which describes what kind of program sent them.
+#[allow(missing_docs)] // The names are from RFC 5424
+#[non_exhaustive]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+pub enum Facility {
+    Kern,
+    #[default]
+    User,
+    Mail,
+    Daemon,
+    Auth,
+    Syslog,
+    Lpr,
+    News,
+    Uucp,
+    Cron,
+    AuthPriv,
+    Ftp,
+    Local0,
+    Local1,
+    Local2,
+    Local3,
+    Local4,
+    Local5,
+    Local6,
+    Local7,
+}
+
+impl Facility {
+    fn code(self) -> u8 {
+        match self {
+            Self::Kern => 0,
+            Self::User => 1,
+            Self::Mail => 2,
+            Self::Daemon => 3,
+            Self::Auth => 4,
+            Self::Syslog => 5,
+            Self::Lpr => 6,
+            Self::News => 7,
+            Self::Uucp => 8,
+            Self::Cron => 9,
+            Self::AuthPriv => 10,
+            Self::Ftp => 11,
+            Self::Local0 => 16,
+            Self::Local1 => 17,
+            Self::Local2 => 18,
+            Self::Local3 => 19,
+            Self::Local4 => 20,
+            Self::Local5 => 21,
+            Self::Local6 => 22,
+            Self::Local7 => 23,
+        }
+    }
+}
+
+/// The format of syslog messages.
+#[non_exhaustive]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+pub enum Protocol {
+    /// The format of [RFC 5424](https://datatracker.ietf.org/doc/html/rfc5424).
+    #[default]
+    Rfc5424,
+    /// The legacy BSD format of [RFC 3164](https://datatracker.ietf.org/doc/html/rfc3164).
+    Rfc3164,
+}
+
+/// Formats records as syslog messages.
+///
+/// The hostname, app-name and procid default to the hostname of the machine, the file name
+/// of the current executable and the current process ID.
+///
+/// See the [module documentation][self] for more details.
+#[derive(Clone, Debug)]
+pub struct Syslog {
+    facility: Facility,
+    protocol: Protocol,
+    hostname: Option<String>,
+    app_name: Option<String>,
+    procid: Option<String>,
+    sd_id: String,
+}
+
+impl Syslog {
+    /// Creates the format with defaults.
+    pub fn new() -> Self {
+        Self {
+            facility: Facility::default(),
+            protocol: Protocol::default(),
+            hostname: hostname(),
+            app_name: std::env::current_exe()
+                .ok()
+                .and_then(|exe| Some(exe.file_name()?.to_string_lossy().into_owned())),
+            procid: Some(std::process::id().to_string()),
+            sd_id: "kv@32473".to_owned(),
+        }
+    }
+
+    /// Sets the facility of the messages.
+    pub fn facility(&mut self, facility: Facility) -> &mut Self {
+        self.facility = facility;
+        self
+    }
+
+    /// Sets the format of the messages.
+    pub fn protocol(&mut self, protocol: Protocol) -> &mut Self {
+        self.protocol = protocol;
+        self
+    }
+
+    /// Sets the hostname of the messages.
+    pub fn hostname(&mut self, hostname: impl Into<String>) -> &mut Self {
+        self.hostname = Some(hostname.into());
+        self
+    }
+
+    /// Sets the app-name of the messages, also known as the tag in RFC 3164.
+    pub fn app_name(&mut self, app_name: impl Into<String>) -> &mut Self {
+        self.app_name = Some(app_name.into());
+        self
+    }
+
+    /// Sets the procid of the messages.
+    pub fn procid(&mut self, procid: impl Into<String>) -> &mut Self {
+        self.procid = Some(procid.into());
+        self
+    }
+
+    /// Sets the SD-ID of the structured data element with the key-values of a record.
+    ///
+    /// The default is `kv@32473`, using the enterprise number reserved for documentation.
+    pub fn structured_data_id(&mut self, sd_id: impl Into<String>) -> &mut Self {
+        self.sd_id = sd_id.into();
+        self
+    }
+
+    /// Formats `record` as a syslog message, without a trailing newline.
+    ///
+    /// This can be used in a custom format, like to write syslog messages to stderr for a
+    /// service manager that forwards them to syslog.
+    pub fn format(&self, buf: &mut Formatter, record: &Record<'_>) -> io::Result<()> {
+        self.write(buf, record, SystemTime::now())
+    }
+
+    fn write(
+        &self,
+        buf: &mut impl io::Write,
+        record: &Record<'_>,
+        now: SystemTime,
+    ) -> io::Result<()> {
+        let priority = u16::from(self.facility.code()) * 8 + u16::from(severity(record.level()));
+        match self.protocol {
+            Protocol::Rfc5424 => {
+                write!(
+                    buf,
+                    "<{priority}>1 {} {} {} {} {} ",
+                    time::rfc3339(now),
+                    header_field(self.hostname.as_deref(), 255),
+                    header_field(self.app_name.as_deref(), 48),
+                    header_field(self.procid.as_deref(), 128),
+                    header_field(Some(record.target()), 32),
+                )?;
+                self.write_structured_data(buf, record)?;
+                write!(buf, " {}", record.args())
+            }
+            Protocol::Rfc3164 => {
+                write!(
+                    buf,
+                    "<{priority}>{} {} {}",
+                    time::rfc3164(now),
+                    self.hostname.as_deref().unwrap_or("localhost"),
+                    self.app_name.as_deref().unwrap_or("-"),
+                )?;
+                if let Some(procid) = &self.procid {
+                    write!(buf, "[{procid}]")?;
+                }
+                write!(buf, ": {}", record.args())
+            }
+        }
+    }
+
+    #[cfg(not(feature = "kv"))]
+    fn write_structured_data(&self, buf: &mut impl io::Write, _: &Record<'_>) -> io::Result<()> {
+        buf.write_all(b"-")
+    }
+
+    #[cfg(feature = "kv")]
+    fn write_structured_data(
+        &self,
+        buf: &mut impl io::Write,
+        record: &Record<'_>,
+    ) -> io::Result<()> {
+        use std::io::Write as _;
+
+        use log::kv::{Error, Key, Value, VisitSource};
+
+        struct Params<W>(W);
+
+        impl<'kvs, W: io::Write> VisitSource<'kvs> for Params<W> {
+            fn visit_pair(&mut self, key: Key<'_>, value: Value<'kvs>) -> Result<(), Error> {
+                write!(self.0, " {}=\"", param_name(key.as_str()))?;
+                write!(Escape(&mut self.0), "{value}")?;
+                self.0.write_all(b"\"")?;
+                Ok(())
+            }
+        }
+
+        let kvs = record.key_values();
+        if kvs.count() == 0 {
+            return buf.write_all(b"-");
+        }
+
+        write!(buf, "[{}", param_name(&self.sd_id))?;
+        kvs.visit(&mut Params(&mut *buf))
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+        buf.write_all(b"]")
+    }
+}
+
+impl Default for Syslog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub(crate) fn severity(level: Level) -> u8 {
+    match level {
+        Level::Error => 3,
+        Level::Warn => 4,
+        Level::Info => 6,
+        Level::Debug | Level::Trace => 7,
+    }
+}
+
+/// A header field of RFC 5424, which is printable ASCII without spaces, or `-` if empty.
+struct HeaderField<'a> {
+    value: &'a str,
+    max_len: usize,
+}
+
+fn header_field(value: Option<&str>, max_len: usize) -> HeaderField<'_> {
+    HeaderField {
+        value: value.unwrap_or_default(),
+        max_len,
+    }
+}
+
+impl std::fmt::Display for HeaderField<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use std::fmt::Write as _;
+
+        if self.value.is_empty() {
+            return f.write_char('-');
+        }
+        for c in self.value.chars().take(self.max_len) {
+            f.write_char(if c.is_ascii_graphic() { c } else { '_' })?;
+        }
+        Ok(())
+    }
+}
+
+/// The name of a structured data element or parameter: at most 32 characters of printable
+/// ASCII, except for `=`, `]`, `"` and spaces.
+#[cfg(feature = "kv")]
+fn param_name(name: &str) -> String {
+    name.chars()
+        .take(32)
+        .map(|c| match c {
+            '=' | ']' | '"' => '_',
+            c if c.is_ascii_graphic() => c,
+            _ => '_',
+        })
+        .collect()
+}
+
+/// Escapes a structured data parameter value.
+#[cfg(feature = "kv")]
+struct Escape<W>(W);
+
+#[cfg(feature = "kv")]
+impl<W: io::Write> io::Write for Escape<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        for chunk in buf.split_inclusive(|b| matches!(b, b'"' | b'\\' | b']')) {
+            match chunk.split_last() {
+                Some((last @ (b'"' | b'\\' | b']'), rest)) => {
+                    self.0.write_all(rest)?;
+                    self.0.write_all(&[b'\\', *last])?;
+                }
+                _ => self.0.write_all(chunk)?,
+            }
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush()
+    }
+}
+
+fn hostname() -> Option<String> {
+    ["/proc/sys/kernel/hostname", "/etc/hostname"]
+        .iter()
+        .filter_map(|path| std::fs::read_to_string(path).ok())
+        .map(|hostname| hostname.trim().to_owned())
+        .find(|hostname| !hostname.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, SystemTime};
+
+    use log::{Level, Record};
+
+    use super::{Facility, Protocol, Syslog};
+
+    fn syslog() -> Syslog {
+        let mut syslog = Syslog::new();
+        syslog
+            .facility(Facility::Daemon)
+            .hostname("box")
+            .app_name("myapp")
+            .procid("42");
+        syslog
+    }
+
+    fn write(syslog: &Syslog, record: &Record<'_>) -> String {
+        // 2024-02-29T13:04:05.123456Z
+        let now = SystemTime::UNIX_EPOCH + Duration::new(1_709_211_845, 123_456_789);
+        let mut buf = Vec::new();
+        syslog.write(&mut buf, record, now).unwrap();
+        String::from_utf8(buf).unwrap()
+    }
+
+    #[test]
+    fn format_rfc5424() {
+        let record = Record::builder()
+            .level(Level::Warn)
+            .target("myapp::db")
+            .args(format_args!("slow query"))
+            .build();
+
+        assert_eq!(
+            write(&syslog(), &record),
+            "<28>1 2024-02-29T13:04:05.123456Z box myapp 42 myapp::db - slow query"
+        );
+    }
+
+    #[test]
+    fn format_rfc5424_nil_and_invalid_fields() {
+        let mut syslog = syslog();
+        syslog.hostname("").app_name("my app");
+        let record = Record::builder()
+            .level(Level::Trace)
+            .target("a-very-long-target-name::that-goes-on::and-on")
+            .args(format_args!("hi"))
+            .build();
+
+        assert_eq!(
+            write(&syslog, &record),
+            "<31>1 2024-02-29T13:04:05.123456Z - my_app 42 a-very-long-target-name::that-go - hi"
+        );
+    }
+
+    #[test]
+    fn format_rfc3164() {
+        let mut syslog = syslog();
+        syslog
+            .protocol(Protocol::Rfc3164)
+            .facility(Facility::Local7);
+        let record = Record::builder()
+            .level(Level::Error)
+            .args(format_args!("disk full"))
+            .build();
+
+        assert_eq!(
+            write(&syslog, &record),
+            "<187>Feb 29 13:04:05 box myapp[42]: disk full"
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn log_to_unix_socket() {
+        use std::os::unix::net::UnixDatagram;
+
+        use log::Log as _;
+
+        use super::SyslogSocket;
+        use crate::{Builder, Framing};
+
+        let path = std::env::temp_dir().join(format!("env_logger-syslog-{}", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let server = UnixDatagram::bind(&path).unwrap();
+
+        let logger = Builder::new()
+            .parse_filters("info")
+            // Ignored, since each record is a datagram of its own
+            .framing(Framing::OctetCounting)
+            .syslog(syslog(), SyslogSocket::unix(&path).unwrap())
+            .build();
+        for (level, message) in [
+            (Level::Info, "first"),
+            (Level::Debug, "hidden"),
+            (Level::Error, "second"),
+        ] {
+            logger.log(
+                &Record::builder()
+                    .level(level)
+                    .target("myapp")
+                    .args(format_args!("{message}"))
+                    .build(),
+            );
+        }
+
+        let mut buf = [0; 256];
+        let len = server.recv(&mut buf).unwrap();
+        let message = std::str::from_utf8(&buf[..len]).unwrap();
+        assert!(message.starts_with("<30>1 "), "{message}");
+        assert!(
+            message.ends_with(" box myapp 42 myapp - first"),
+            "{message}"
+        );
+
+        let len = server.recv(&mut buf).unwrap();
+        let message = std::str::from_utf8(&buf[..len]).unwrap();
+        assert!(message.starts_with("<27>1 "), "{message}");
+        assert!(message.ends_with(" - second"), "{message}");
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    #[cfg(feature = "kv")]
+    fn format_structured_data() {
+        let kvs: &[(&str, &str)] = &[("user id", "7"), ("query", r#"a="b" [c\d]"#)];
+        let record = Record::builder()
+            .level(Level::Info)
+            .target("db")
+            .key_values(&kvs)
+            .args(format_args!("query"))
+            .build();
+
+        assert_eq!(
+            write(&syslog(), &record),
+            r#"<30>1 2024-02-29T13:04:05.123456Z box myapp 42 db [kv@32473 user_id="7" query="a=\"b\" [c\\d\]"] query"#
+        );
+    }
+}
@@ -0,0 +1,96 @@
+use std::io;
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+#[cfg(unix)]
+use std::os::unix::net::UnixDatagram;
+#[cfg(unix)]
+use std::path::Path;
+
+/// A datagram socket to a syslog daemon.
+///
+/// Each write is sent as a single message.
+#[derive(Debug)]
+pub struct SyslogSocket {
+    inner: Inner,
+}
+
+#[derive(Debug)]
+enum Inner {
+    #[cfg(unix)]
+    Unix(UnixDatagram),
+    Udp(UdpSocket),
+}
+
+impl SyslogSocket {
+    /// Connects to the local syslog daemon at `path`, usually `/dev/log`.
+    #[cfg(unix)]
+    pub fn unix(path: impl AsRef<Path>) -> io::Result<Self> {
+        let socket = UnixDatagram::unbound()?;
+        socket.connect(path)?;
+        Ok(Self {
+            inner: Inner::Unix(socket),
+        })
+    }
+
+    /// Connects to a syslog daemon listening for UDP at `addr`, usually on port 514.
+    pub fn udp(addr: impl ToSocketAddrs) -> io::Result<Self> {
+        let mut last_err = None;
+        for addr in addr.to_socket_addrs()? {
+            let local: SocketAddr = if addr.is_ipv4() {
+                ([0, 0, 0, 0], 0).into()
+            } else {
+                ([0u16; 8], 0).into()
+            };
+            match UdpSocket::bind(local).and_then(|socket| {
+                socket.connect(addr)?;
+                Ok(socket)
+            }) {
+                Ok(socket) => {
+                    return Ok(Self {
+                        inner: Inner::Udp(socket),
+                    })
+                }
+                Err(err) => last_err = Some(err),
+            }
+        }
+        Err(last_err.unwrap_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "no addresses to connect to")
+        }))
+    }
+}
+
+impl io::Write for SyslogSocket {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match &self.inner {
+            #[cfg(unix)]
+            Inner::Unix(socket) => socket.send(buf),
+            Inner::Udp(socket) => socket.send(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write as _;
+    use std::net::UdpSocket;
+
+    use super::SyslogSocket;
+
+    #[test]
+    fn send_udp() {
+        let server = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let mut socket = SyslogSocket::udp(server.local_addr().unwrap()).unwrap();
+
+        socket.write_all(b"<14>1 first").unwrap();
+        socket.write_all(b"<14>1 second").unwrap();
+
+        let mut buf = [0; 64];
+        let len = server.recv(&mut buf).unwrap();
+        assert_eq!(&buf[..len], b"<14>1 first");
+        let len = server.recv(&mut buf).unwrap();
+        assert_eq!(&buf[..len], b"<14>1 second");
+    }
+}
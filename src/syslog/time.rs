@@ -0,0 +1,119 @@
+//! Timestamps for syslog headers, in UTC.
+
+use std::fmt;
+use std::time::SystemTime;
+
+/// A broken-down UTC time.
+struct Time {
+    year: i64,
+    month: u32,
+    day: u32,
+    hour: u64,
+    minute: u64,
+    second: u64,
+    micros: u32,
+}
+
+impl Time {
+    fn new(time: SystemTime) -> Self {
+        // Times before the epoch are clamped to it, syslog daemons wouldn't take them anyway
+        let since_epoch = time
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default();
+        let secs = since_epoch.as_secs();
+        let (year, month, day) = civil_from_days((secs / 86_400) as i64);
+
+        Self {
+            year,
+            month,
+            day,
+            hour: secs % 86_400 / 3600,
+            minute: secs % 3600 / 60,
+            second: secs % 60,
+            micros: since_epoch.subsec_micros(),
+        }
+    }
+}
+
+/// Formats `time` like `2024-02-29T13:04:05.123456Z`.
+pub(super) fn rfc3339(time: SystemTime) -> impl fmt::Display {
+    struct Rfc3339(Time);
+
+    impl fmt::Display for Rfc3339 {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            let t = &self.0;
+            write!(
+                f,
+                "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:06}Z",
+                t.year, t.month, t.day, t.hour, t.minute, t.second, t.micros
+            )
+        }
+    }
+
+    Rfc3339(Time::new(time))
+}
+
+/// Formats `time` like `Feb 29 13:04:05`.
+pub(super) fn rfc3164(time: SystemTime) -> impl fmt::Display {
+    struct Rfc3164(Time);
+
+    impl fmt::Display for Rfc3164 {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            const MONTHS: [&str; 12] = [
+                "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+            ];
+
+            let t = &self.0;
+            write!(
+                f,
+                "{} {:2} {:02}:{:02}:{:02}",
+                MONTHS[t.month as usize - 1],
+                t.day,
+                t.hour,
+                t.minute,
+                t.second
+            )
+        }
+    }
+
+    Rfc3164(Time::new(time))
+}
+
+/// Converts days since 1970-01-01 to a `(year, month, day)` date.
+///
+/// This is Howard Hinnant's `civil_from_days` algorithm.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, SystemTime};
+
+    use super::{civil_from_days, rfc3164, rfc3339};
+
+    #[test]
+    fn civil_dates() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(59), (1970, 3, 1));
+        assert_eq!(civil_from_days(11_016), (2000, 2, 29));
+        assert_eq!(civil_from_days(19_782), (2024, 2, 29));
+        assert_eq!(civil_from_days(2_932_896), (9999, 12, 31));
+    }
+
+    #[test]
+    fn format_times() {
+        let time = SystemTime::UNIX_EPOCH + Duration::new(1_704_517_445, 9_999);
+        assert_eq!(rfc3339(time).to_string(), "2024-01-06T05:04:05.000009Z");
+        assert_eq!(rfc3164(time).to_string(), "Jan  6 05:04:05");
+    }
+}
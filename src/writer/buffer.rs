@@ -1,6 +1,14 @@
+use std::cell::Cell;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, Condvar, MutexGuard, PoisonError};
+use std::thread;
+use std::time::{Duration, Instant};
 use std::{io, sync::Mutex};
 
-use crate::writer::WriteStyle;
+use log::Level;
+
+use crate::writer::target::CallbackFn;
+use crate::writer::{Framing, RecordMeta, WriteStyle};
 
 #[derive(Debug)]
 pub(crate) struct BufferWriter {
@@ -31,6 +39,26 @@ impl BufferWriter {
         }
     }
 
+    /// Writes to standard error in blocks.
+    pub(crate) fn buffered_stderr(buffering: Buffering, write_style: WriteStyle) -> Self {
+        BufferWriter {
+            target: WritableTarget::BufferedStderr(Block::new(buffering, |buf| {
+                write_stderr(buf, WriteStyle::Always)
+            })),
+            write_style,
+        }
+    }
+
+    /// Writes to standard output in blocks.
+    pub(crate) fn buffered_stdout(buffering: Buffering, write_style: WriteStyle) -> Self {
+        BufferWriter {
+            target: WritableTarget::BufferedStdout(Block::new(buffering, |buf| {
+                write_stdout(buf, WriteStyle::Always)
+            })),
+            write_style,
+        }
+    }
+
     pub(crate) fn pipe(
         pipe: Box<Mutex<dyn io::Write + Send + 'static>>,
         write_style: WriteStyle,
@@ -41,6 +69,20 @@ impl BufferWriter {
         }
     }
 
+    pub(crate) fn callback(callback: Box<CallbackFn>, write_style: WriteStyle) -> Self {
+        BufferWriter {
+            target: WritableTarget::Callback(callback),
+            write_style,
+        }
+    }
+
+    pub(crate) fn raw_pipe(pipe: Box<Mutex<dyn io::Write + Send + 'static>>) -> Self {
+        BufferWriter {
+            target: WritableTarget::RawPipe(pipe),
+            write_style: WriteStyle::Never,
+        }
+    }
+
     pub(crate) fn write_style(&self) -> WriteStyle {
         self.write_style
     }
@@ -49,59 +91,321 @@ impl BufferWriter {
         Buffer(Vec::new())
     }
 
-    pub(crate) fn print(&self, buf: &Buffer) -> io::Result<()> {
+    pub(crate) fn print(&self, buf: &Buffer, meta: &RecordMeta<'_>) -> io::Result<()> {
+        self.write(buf.as_bytes(), meta, self.write_style)
+    }
+
+    /// Writes a record in a frame.
+    ///
+    /// Styles are adapted before framing, so the frame isn't changed by stripping them
+    /// afterwards.
+    pub(crate) fn print_framed(
+        &self,
+        buf: &Buffer,
+        meta: &RecordMeta<'_>,
+        framing: Framing,
+    ) -> io::Result<()> {
+        let buf = buf.as_bytes();
+        #[cfg(feature = "color")]
+        let buf = &adapt(buf, self.write_style)?;
+
+        self.write(&framing.frame(buf)?, meta, WriteStyle::Always)
+    }
+
+    #[cfg_attr(not(feature = "color"), allow(unused_variables))]
+    fn write(&self, buf: &[u8], meta: &RecordMeta<'_>, write_style: WriteStyle) -> io::Result<()> {
         #![allow(clippy::print_stdout)] // enabled for tests only
         #![allow(clippy::print_stderr)] // enabled for tests only
 
-        use std::io::Write as _;
-
-        let buf = buf.as_bytes();
         match &self.target {
-            WritableTarget::WriteStdout => {
-                let stream = io::stdout();
+            WritableTarget::WriteStdout => write_stdout(buf, write_style)?,
+            WritableTarget::BufferedStdout(block) => {
+                // The buffer is written out later, so adapt styles as it's added
                 #[cfg(feature = "color")]
-                let stream = anstream::AutoStream::new(stream, self.write_style.into());
-                let mut stream = stream.lock();
-                stream.write_all(buf)?;
-                stream.flush()?;
+                let buf = &adapt(buf, write_style)?;
+                block.push(buf, meta.level())?;
             }
             WritableTarget::PrintStdout => {
                 #[cfg(feature = "color")]
-                let buf = adapt(buf, self.write_style)?;
+                let buf = adapt(buf, write_style)?;
                 #[cfg(feature = "color")]
                 let buf = &buf;
                 let buf = String::from_utf8_lossy(buf);
-                print!("{buf}");
+                printing(|| print!("{buf}"))?;
             }
-            WritableTarget::WriteStderr => {
-                let stream = io::stderr();
+            WritableTarget::WriteStderr => write_stderr(buf, write_style)?,
+            WritableTarget::BufferedStderr(block) => {
+                // The buffer is written out later, so adapt styles as it's added
                 #[cfg(feature = "color")]
-                let stream = anstream::AutoStream::new(stream, self.write_style.into());
-                let mut stream = stream.lock();
-                stream.write_all(buf)?;
-                stream.flush()?;
+                let buf = &adapt(buf, write_style)?;
+                block.push(buf, meta.level())?;
             }
             WritableTarget::PrintStderr => {
                 #[cfg(feature = "color")]
-                let buf = adapt(buf, self.write_style)?;
+                let buf = adapt(buf, write_style)?;
                 #[cfg(feature = "color")]
                 let buf = &buf;
                 let buf = String::from_utf8_lossy(buf);
-                eprint!("{buf}");
+                printing(|| eprint!("{buf}"))?;
             }
             WritableTarget::Pipe(pipe) => {
                 #[cfg(feature = "color")]
-                let buf = adapt(buf, self.write_style)?;
+                let buf = adapt(buf, write_style)?;
                 #[cfg(feature = "color")]
                 let buf = &buf;
                 let mut stream = pipe.lock().expect("no panics while held");
                 stream.write_all(buf)?;
                 stream.flush()?;
             }
+            WritableTarget::RawPipe(pipe) => {
+                let mut stream = pipe.lock().expect("no panics while held");
+                stream.write_all(buf)?;
+                stream.flush()?;
+            }
+            WritableTarget::Callback(callback) => {
+                #[cfg(feature = "color")]
+                let buf = adapt(buf, write_style)?;
+                #[cfg(feature = "color")]
+                let buf = &buf;
+                callback(buf, meta);
+            }
         }
 
         Ok(())
     }
+
+    /// Writes out any buffered output.
+    pub(crate) fn flush(&self) -> io::Result<()> {
+        match &self.target {
+            WritableTarget::BufferedStdout(block) | WritableTarget::BufferedStderr(block) => {
+                block.flush()
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg_attr(not(feature = "color"), allow(unused_variables))]
+fn write_stdout(buf: &[u8], write_style: WriteStyle) -> io::Result<()> {
+    use std::io::Write as _;
+
+    let stream = io::stdout();
+    #[cfg(feature = "color")]
+    let stream = anstream::AutoStream::new(stream, write_style.into());
+    let mut stream = stream.lock();
+    stream.write_all(buf)?;
+    stream.flush()
+}
+
+#[cfg_attr(not(feature = "color"), allow(unused_variables))]
+fn write_stderr(buf: &[u8], write_style: WriteStyle) -> io::Result<()> {
+    use std::io::Write as _;
+
+    let stream = io::stderr();
+    #[cfg(feature = "color")]
+    let stream = anstream::AutoStream::new(stream, write_style.into());
+    let mut stream = stream.lock();
+    stream.write_all(buf)?;
+    stream.flush()
+}
+
+/// When block-buffered output is written out.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Buffering {
+    /// The number of bytes to buffer.
+    pub(crate) capacity: usize,
+    /// How long a record may wait in the buffer.
+    pub(crate) interval: Duration,
+    /// The least severe level of the records that are written out right away.
+    pub(crate) level: Level,
+}
+
+/// Output kept in memory until it's written to the stream in one go.
+///
+/// A timer thread writes the output out once the oldest record has waited for the
+/// interval, so records aren't held back when none follow. It's started with the first
+/// record, and stops when the block is dropped.
+pub(crate) struct Block {
+    shared: Arc<Shared>,
+}
+
+/// The state shared with the timer thread.
+struct Shared {
+    buffering: Buffering,
+    write: Box<WriteFn>,
+    pending: Mutex<Pending>,
+    /// Wakes the timer up when a record is buffered or the block is dropped.
+    wake: Condvar,
+}
+
+type WriteFn = dyn Fn(&[u8]) -> io::Result<()> + Send + Sync;
+
+#[derive(Debug)]
+struct Pending {
+    buf: Vec<u8>,
+    /// When the oldest buffered record was added.
+    since: Option<Instant>,
+    /// Whether the timer thread has been started.
+    timer: bool,
+    dropped: bool,
+}
+
+impl Block {
+    /// Buffers output that is passed to `write` when it's due.
+    fn new(
+        buffering: Buffering,
+        write: impl Fn(&[u8]) -> io::Result<()> + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            shared: Arc::new(Shared {
+                buffering,
+                write: Box::new(write),
+                pending: Mutex::new(Pending {
+                    buf: Vec::with_capacity(buffering.capacity),
+                    since: None,
+                    timer: false,
+                    dropped: false,
+                }),
+                wake: Condvar::new(),
+            }),
+        }
+    }
+
+    /// Adds a record, writing out the buffered output if it's due.
+    ///
+    /// Output is due when the buffer is full, when the oldest record has waited for the
+    /// interval, or when the record is at or above the level.
+    fn push(&self, buf: &[u8], level: Level) -> io::Result<()> {
+        let shared = &self.shared;
+        let mut pending = shared.lock();
+        let since = match pending.since {
+            Some(since) => since,
+            None => {
+                let since = Instant::now();
+                pending.since = Some(since);
+                self.start_timer(&mut pending);
+                shared.wake.notify_one();
+                since
+            }
+        };
+        pending.buf.extend_from_slice(buf);
+
+        if pending.buf.len() >= shared.buffering.capacity
+            || level <= shared.buffering.level
+            || since.elapsed() >= shared.buffering.interval
+        {
+            pending.write(&shared.write)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Writes out the buffered output.
+    fn flush(&self) -> io::Result<()> {
+        self.shared.lock().write(&self.shared.write)
+    }
+
+    /// Starts the timer thread, if it isn't running yet.
+    fn start_timer(&self, pending: &mut Pending) {
+        if pending.timer {
+            return;
+        }
+
+        let shared = self.shared.clone();
+        // Output is still written out by later records if the thread can't be spawned,
+        // and it's tried again with the next block of output
+        pending.timer = thread::Builder::new()
+            .name("env_logger-flush".to_owned())
+            .spawn(move || shared.flush_when_due())
+            .is_ok();
+    }
+}
+
+impl Drop for Block {
+    fn drop(&mut self) {
+        self.shared.lock().dropped = true;
+        self.shared.wake.notify_one();
+    }
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, Pending> {
+        self.pending.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Writes out the buffered output whenever the oldest record has waited for the
+    /// interval, until the block is dropped.
+    fn flush_when_due(&self) {
+        let mut pending = self.lock();
+        loop {
+            if pending.dropped {
+                return;
+            }
+
+            let Some(since) = pending.since else {
+                pending = self
+                    .wake
+                    .wait(pending)
+                    .unwrap_or_else(PoisonError::into_inner);
+                continue;
+            };
+            let due = since + self.buffering.interval;
+            let now = Instant::now();
+            if now < due {
+                pending = self
+                    .wake
+                    .wait_timeout(pending, due - now)
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .0;
+                continue;
+            }
+
+            // There's no record to report the error for, so it's dropped like the
+            // output, as when writing out fails on a later record
+            let _ = pending.write(&self.write);
+        }
+    }
+}
+
+impl Pending {
+    /// Passes the buffered output to `write`, and forgets it even if that fails.
+    fn write(&mut self, write: &WriteFn) -> io::Result<()> {
+        if self.buf.is_empty() {
+            return Ok(());
+        }
+
+        let result = write(&self.buf);
+        self.buf.clear();
+        self.since = None;
+        result
+    }
+}
+
+thread_local! {
+    static PRINTING: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Whether the current thread is printing a record with `print!` or `eprint!`.
+///
+/// A panic while printing can't be logged, since printing would panic again.
+pub(crate) fn is_printing() -> bool {
+    PRINTING.try_with(Cell::get).unwrap_or(false)
+}
+
+/// Calls `print`, turning its panic into an error if the output is closed.
+fn printing(print: impl FnOnce()) -> io::Result<()> {
+    struct Printing;
+
+    impl Drop for Printing {
+        fn drop(&mut self) {
+            let _ = PRINTING.try_with(|printing| printing.set(false));
+        }
+    }
+
+    let _ = PRINTING.try_with(|printing| printing.set(true));
+    let _printing = Printing;
+    panic::catch_unwind(AssertUnwindSafe(print))
+        .map_err(|_| io::Error::new(io::ErrorKind::Other, "failed printing the log record"))
 }
 
 #[cfg(feature = "color")]
@@ -134,6 +438,23 @@ impl Buffer {
     pub(crate) fn as_bytes(&self) -> &[u8] {
         &self.0
     }
+
+    /// Inserts `prefix` after each newline written since `start`, unless it ends the buffer.
+    #[cfg(feature = "kv")]
+    pub(crate) fn prefix_lines(&mut self, start: usize, prefix: &[u8]) {
+        if !self.0[start..].contains(&b'\n') {
+            return;
+        }
+
+        let lines = self.0.split_off(start);
+        let mut lines = lines.split_inclusive(|&b| b == b'\n').peekable();
+        while let Some(line) = lines.next() {
+            self.0.extend_from_slice(line);
+            if line.ends_with(b"\n") && lines.peek().is_some() {
+                self.0.extend_from_slice(prefix);
+            }
+        }
+    }
 }
 
 impl std::fmt::Debug for Buffer {
@@ -148,14 +469,22 @@ impl std::fmt::Debug for Buffer {
 pub(crate) enum WritableTarget {
     /// Logs will be written to standard output.
     WriteStdout,
+    /// Logs will be written to standard output in blocks.
+    BufferedStdout(Block),
     /// Logs will be printed to standard output.
     PrintStdout,
     /// Logs will be written to standard error.
     WriteStderr,
+    /// Logs will be written to standard error in blocks.
+    BufferedStderr(Block),
     /// Logs will be printed to standard error.
     PrintStderr,
     /// Logs will be sent to a custom pipe.
     Pipe(Box<Mutex<dyn io::Write + Send + 'static>>),
+    /// Logs will be sent to a custom pipe as-is, without adapting them for styles.
+    RawPipe(Box<Mutex<dyn io::Write + Send + 'static>>),
+    /// Logs will be passed to a function.
+    Callback(Box<CallbackFn>),
 }
 
 impl std::fmt::Debug for WritableTarget {
@@ -165,11 +494,107 @@ impl std::fmt::Debug for WritableTarget {
             "{}",
             match self {
                 Self::WriteStdout => "stdout",
+                Self::BufferedStdout(_) => "stdout",
                 Self::PrintStdout => "stdout",
                 Self::WriteStderr => "stderr",
+                Self::BufferedStderr(_) => "stderr",
                 Self::PrintStderr => "stderr",
                 Self::Pipe(_) => "pipe",
+                Self::RawPipe(_) => "pipe",
+                Self::Callback(_) => "callback",
             }
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+    use std::time::{Duration, Instant};
+
+    use log::Level;
+
+    use super::{Block, Buffering};
+
+    type Written = Arc<Mutex<Vec<String>>>;
+
+    fn block(capacity: usize, interval: Duration) -> (Block, Written) {
+        let written = Written::default();
+        let block = Block::new(
+            Buffering {
+                capacity,
+                interval,
+                level: Level::Warn,
+            },
+            {
+                let written = written.clone();
+                move |buf| {
+                    written
+                        .lock()
+                        .unwrap()
+                        .push(String::from_utf8(buf.to_vec()).unwrap());
+                    Ok(())
+                }
+            },
+        );
+        (block, written)
+    }
+
+    #[test]
+    fn write_out_when_due() {
+        let (block, written) = block(8, Duration::from_secs(60));
+
+        block.push(b"a\n", Level::Info).unwrap();
+        block.push(b"b\n", Level::Info).unwrap();
+        assert!(written.lock().unwrap().is_empty());
+
+        // At the flush level
+        block.push(b"c\n", Level::Warn).unwrap();
+        // Full
+        block.push(b"defg\n", Level::Info).unwrap();
+        block.push(b"hijk\n", Level::Info).unwrap();
+        // Flushed
+        block.push(b"l\n", Level::Info).unwrap();
+        block.flush().unwrap();
+        block.flush().unwrap();
+
+        assert_eq!(
+            *written.lock().unwrap(),
+            ["a\nb\nc\n", "defg\nhijk\n", "l\n"]
+        );
+    }
+
+    #[test]
+    fn write_out_after_interval() {
+        let (block, written) = block(1024, Duration::ZERO);
+
+        block.push(b"a\n", Level::Info).unwrap();
+        assert_eq!(*written.lock().unwrap(), ["a\n"]);
+    }
+
+    #[test]
+    fn write_out_after_interval_without_records() {
+        let (block, written) = block(1024, Duration::from_millis(20));
+
+        block.push(b"a\n", Level::Info).unwrap();
+        block.push(b"b\n", Level::Info).unwrap();
+        assert!(written.lock().unwrap().is_empty());
+
+        // Written out by the timer, since no record follows
+        let start = Instant::now();
+        while written.lock().unwrap().is_empty() {
+            assert!(start.elapsed() < Duration::from_secs(10), "not written out");
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        assert_eq!(*written.lock().unwrap(), ["a\nb\n"]);
+
+        // And again for the next block
+        block.push(b"c\n", Level::Info).unwrap();
+        let start = Instant::now();
+        while written.lock().unwrap().len() < 2 {
+            assert!(start.elapsed() < Duration::from_secs(10), "not written out");
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        assert_eq!(*written.lock().unwrap(), ["a\nb\n", "c\n"]);
+    }
+}
@@ -0,0 +1,148 @@
+use std::io;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+
+use super::{Buffer, RecordMeta, Target, Writer};
+
+/// What to do when writing a record to the target fails.
+///
+/// Whatever the policy, failures are counted by [`WriteErrors`].
+#[non_exhaustive]
+#[derive(Default)]
+pub enum WriteErrorPolicy {
+    /// Drop the record.
+    #[default]
+    Ignore,
+    /// Call a function with the error, then drop the record.
+    Callback(Box<dyn Fn(&io::Error) + Send + Sync + 'static>),
+    /// Write the record to another target instead, like stderr when a file is full.
+    ///
+    /// The record is dropped if that fails too.
+    Fallback(Target),
+    /// Drop the record, and stop writing to the target after this many failures in a row.
+    ///
+    /// Writing can be enabled again with [`WriteErrors::enable`].
+    DisableAfter(u64),
+}
+
+impl WriteErrorPolicy {
+    /// Calls `f` with each error.
+    pub fn callback(f: impl Fn(&io::Error) + Send + Sync + 'static) -> Self {
+        Self::Callback(Box::new(f))
+    }
+}
+
+impl std::fmt::Debug for WriteErrorPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Ignore => f.write_str("Ignore"),
+            Self::Callback(_) => f.write_str("Callback"),
+            Self::Fallback(target) => f.debug_tuple("Fallback").field(target).finish(),
+            Self::DisableAfter(count) => f.debug_tuple("DisableAfter").field(count).finish(),
+        }
+    }
+}
+
+/// The write errors of a logger.
+///
+/// This is a handle that can be cloned and kept after the logger is installed, like to
+/// report the errors as a metric.
+#[derive(Clone, Debug, Default)]
+pub struct WriteErrors {
+    inner: Arc<Counters>,
+}
+
+#[derive(Debug, Default)]
+struct Counters {
+    total: AtomicU64,
+    in_a_row: AtomicU64,
+    disabled: AtomicBool,
+}
+
+impl WriteErrors {
+    /// The number of records that failed to be written to the target.
+    pub fn count(&self) -> u64 {
+        self.inner.total.load(Ordering::Relaxed)
+    }
+
+    /// Whether writing to the target was disabled by [`WriteErrorPolicy::DisableAfter`].
+    pub fn is_disabled(&self) -> bool {
+        self.inner.disabled.load(Ordering::Relaxed)
+    }
+
+    /// Writes to the target again after it was disabled.
+    pub fn enable(&self) {
+        self.inner.in_a_row.store(0, Ordering::Relaxed);
+        self.inner.disabled.store(false, Ordering::Relaxed);
+    }
+
+    fn succeeded(&self) {
+        if self.inner.in_a_row.load(Ordering::Relaxed) != 0 {
+            self.inner.in_a_row.store(0, Ordering::Relaxed);
+        }
+    }
+
+    /// Counts a failure, returning the number of failures in a row.
+    fn failed(&self) -> u64 {
+        self.inner.total.fetch_add(1, Ordering::Relaxed);
+        self.inner.in_a_row.fetch_add(1, Ordering::Relaxed) + 1
+    }
+}
+
+/// A built [`WriteErrorPolicy`].
+pub(super) enum ErrorHandler {
+    Ignore,
+    Callback(Box<dyn Fn(&io::Error) + Send + Sync + 'static>),
+    Fallback(Box<Writer>),
+    DisableAfter(u64),
+}
+
+impl ErrorHandler {
+    pub(super) fn new(policy: WriteErrorPolicy, fallback: impl FnOnce(Target) -> Writer) -> Self {
+        match policy {
+            WriteErrorPolicy::Ignore => Self::Ignore,
+            WriteErrorPolicy::Callback(f) => Self::Callback(f),
+            WriteErrorPolicy::Fallback(target) => Self::Fallback(Box::new(fallback(target))),
+            WriteErrorPolicy::DisableAfter(count) => Self::DisableAfter(count),
+        }
+    }
+
+    /// Records the result of writing `buf`, handling any error.
+    pub(super) fn handle(
+        &self,
+        result: &io::Result<()>,
+        buf: &Buffer,
+        meta: &RecordMeta<'_>,
+        errors: &WriteErrors,
+    ) {
+        let err = match result {
+            Ok(()) => return errors.succeeded(),
+            Err(err) => err,
+        };
+
+        let in_a_row = errors.failed();
+        match self {
+            Self::Ignore => {}
+            Self::Callback(f) => f(err),
+            Self::Fallback(writer) => {
+                let _ = writer.print(buf, meta);
+            }
+            Self::DisableAfter(count) => {
+                if in_a_row >= *count {
+                    errors.inner.disabled.store(true, Ordering::Relaxed);
+                }
+            }
+        }
+    }
+}
+
+impl std::fmt::Debug for ErrorHandler {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Ignore => f.write_str("Ignore"),
+            Self::Callback(_) => f.write_str("Callback"),
+            Self::Fallback(writer) => f.debug_tuple("Fallback").field(writer).finish(),
+            Self::DisableAfter(count) => f.debug_tuple("DisableAfter").field(count).finish(),
+        }
+    }
+}
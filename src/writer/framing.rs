@@ -0,0 +1,79 @@
+use std::io;
+
+/// How records are delimited in the output.
+///
+/// Apart from [`Framing::Newline`], a trailing newline is left out of the record, so the
+/// format's own suffix doesn't end up in the frame.
+#[non_exhaustive]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+pub enum Framing {
+    /// Each record ends with a newline, which is added if the format doesn't end it with one.
+    #[default]
+    Newline,
+    /// Each record ends with a NUL byte.
+    Nul,
+    /// Each record is prefixed with its length in bytes, as a big-endian `u32`.
+    LengthPrefixed,
+    /// Each record is prefixed with its length in bytes and a space, as in
+    /// [RFC 6587](https://datatracker.ietf.org/doc/html/rfc6587#section-3.4.1).
+    OctetCounting,
+}
+
+impl Framing {
+    /// Frames a formatted record.
+    pub(crate) fn frame(self, record: &[u8]) -> io::Result<Vec<u8>> {
+        let trimmed = record.strip_suffix(b"\n").unwrap_or(record);
+        let framed = match self {
+            Self::Newline => {
+                let mut framed = record.to_vec();
+                if !framed.ends_with(b"\n") {
+                    framed.push(b'\n');
+                }
+                framed
+            }
+            Self::Nul => {
+                let mut framed = trimmed.to_vec();
+                framed.push(b'\0');
+                framed
+            }
+            Self::LengthPrefixed => {
+                let len = u32::try_from(trimmed.len()).map_err(|_| {
+                    io::Error::new(io::ErrorKind::InvalidInput, "record is too long to frame")
+                })?;
+                let mut framed = len.to_be_bytes().to_vec();
+                framed.extend_from_slice(trimmed);
+                framed
+            }
+            Self::OctetCounting => {
+                let mut framed = format!("{} ", trimmed.len()).into_bytes();
+                framed.extend_from_slice(trimmed);
+                framed
+            }
+        };
+        Ok(framed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Framing;
+
+    #[test]
+    fn frame_records() {
+        let frame = |framing: Framing, record: &str| framing.frame(record.as_bytes()).unwrap();
+
+        assert_eq!(frame(Framing::Newline, "first\n"), b"first\n");
+        assert_eq!(frame(Framing::Newline, "second"), b"second\n");
+
+        assert_eq!(frame(Framing::Nul, "multi\nline\n"), b"multi\nline\0");
+        assert_eq!(frame(Framing::Nul, "second"), b"second\0");
+
+        assert_eq!(
+            frame(Framing::LengthPrefixed, "multi\nline\n"),
+            b"\0\0\0\x0amulti\nline"
+        );
+
+        assert_eq!(frame(Framing::OctetCounting, "first\n"), b"5 first");
+        assert_eq!(frame(Framing::OctetCounting, "ünï\n"), "5 ünï".as_bytes());
+    }
+}
@@ -0,0 +1,78 @@
+use super::Target;
+
+/// Whether `target` is a standard stream connected to the systemd journal.
+///
+/// systemd sets `JOURNAL_STREAM` to the device and inode of the stream it connects the
+/// output of a service to, so it can be told apart from a stream redirected elsewhere by
+/// the service itself or a child process inheriting the variable.
+#[cfg(unix)]
+pub(super) fn is_journal_stream(target: &Target) -> bool {
+    std::env::var_os("JOURNAL_STREAM")
+        .and_then(|value| Some(matches_journal_stream(target, value.to_str()?)))
+        .unwrap_or(false)
+}
+
+#[cfg(not(unix))]
+pub(super) fn is_journal_stream(_: &Target) -> bool {
+    false
+}
+
+/// Whether `target` is the stream identified by the `JOURNAL_STREAM` value `value`.
+#[cfg(unix)]
+fn matches_journal_stream(target: &Target, value: &str) -> bool {
+    use std::fs::File;
+    use std::io;
+    use std::os::fd::AsFd;
+    use std::os::unix::fs::MetadataExt as _;
+
+    fn metadata(stream: impl AsFd) -> io::Result<std::fs::Metadata> {
+        File::from(stream.as_fd().try_clone_to_owned()?).metadata()
+    }
+
+    let Some(expected) = parse_journal_stream(value) else {
+        return false;
+    };
+    let metadata = match target {
+        Target::Stdout => metadata(io::stdout()),
+        Target::Stderr | Target::Split(_) => metadata(io::stderr()),
+        Target::Pipe(_) | Target::Callback(_) => return false,
+    };
+    metadata.is_ok_and(|metadata| (metadata.dev(), metadata.ino()) == expected)
+}
+
+/// Parses the `device:inode` value of `JOURNAL_STREAM`.
+#[cfg_attr(not(unix), allow(dead_code))]
+fn parse_journal_stream(value: &str) -> Option<(u64, u64)> {
+    let (dev, ino) = value.split_once(':')?;
+    Some((dev.parse().ok()?, ino.parse().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_journal_stream;
+
+    #[test]
+    fn parse_journal_stream_value() {
+        assert_eq!(parse_journal_stream("8:12345"), Some((8, 12345)));
+        assert_eq!(parse_journal_stream("8"), None);
+        assert_eq!(parse_journal_stream("8:x"), None);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn match_journal_stream() {
+        use std::os::unix::fs::MetadataExt as _;
+
+        use super::{matches_journal_stream, Target};
+
+        assert!(!matches_journal_stream(&Target::Stderr, "0:0"));
+        if let Ok(stderr) = std::fs::metadata("/dev/stderr") {
+            let value = format!("{}:{}", stderr.dev(), stderr.ino());
+            assert!(matches_journal_stream(&Target::Stderr, &value));
+            assert!(!matches_journal_stream(
+                &Target::Pipe(Box::new(std::io::sink())),
+                &value
+            ));
+        }
+    }
+}
@@ -1,13 +1,23 @@
 mod buffer;
+mod error;
+mod framing;
+mod journal;
 mod target;
 
+use std::time::Duration;
 use std::{io, mem, sync::Mutex};
 
-use buffer::BufferWriter;
+use log::Level;
 
-pub(crate) use buffer::Buffer;
+use buffer::{BufferWriter, Buffering};
+use error::ErrorHandler;
 
-pub use target::Target;
+pub(crate) use buffer::{is_printing, Buffer};
+
+pub use error::{WriteErrorPolicy, WriteErrors};
+pub use framing::Framing;
+pub(crate) use target::OwnedRecordMeta;
+pub use target::{RecordMeta, Target};
 
 /// Whether or not to print styles to the target.
 #[allow(clippy::exhaustive_enums)] // By definition don't need more
@@ -49,19 +59,63 @@ impl From<WriteStyle> for anstream::ColorChoice {
 #[derive(Debug)]
 pub(crate) struct Writer {
     inner: BufferWriter,
+    /// The writer for records less severe than the level, when split.
+    split: Option<(Level, BufferWriter)>,
+    framing: Option<Framing>,
+    on_error: ErrorHandler,
+    errors: WriteErrors,
 }
 
 impl Writer {
+    /// Whether to format records with styles.
+    ///
+    /// When split, records are formatted with styles if either stream writes them, and
+    /// the other stream strips them.
     pub(crate) fn write_style(&self) -> WriteStyle {
-        self.inner.write_style()
+        match &self.split {
+            Some((_, split)) if split.write_style() != WriteStyle::Never => split.write_style(),
+            _ => self.inner.write_style(),
+        }
     }
 
     pub(crate) fn buffer(&self) -> Buffer {
         self.inner.buffer()
     }
 
-    pub(crate) fn print(&self, buf: &Buffer) -> io::Result<()> {
-        self.inner.print(buf)
+    /// Writes a formatted record.
+    pub(crate) fn print(&self, buf: &Buffer, meta: &RecordMeta<'_>) -> io::Result<()> {
+        if self.errors.is_disabled() {
+            return Ok(());
+        }
+
+        let stream = self.stream(meta.level());
+        let result = match self.framing {
+            Some(framing) => stream.print_framed(buf, meta, framing),
+            None => stream.print(buf, meta),
+        };
+        self.on_error.handle(&result, buf, meta, &self.errors);
+        result
+    }
+
+    /// The stream for records at `level`.
+    fn stream(&self, level: Level) -> &BufferWriter {
+        match &self.split {
+            Some((split_level, split)) if level > *split_level => split,
+            _ => &self.inner,
+        }
+    }
+
+    pub(crate) fn errors(&self) -> &WriteErrors {
+        &self.errors
+    }
+
+    /// Writes out any buffered output.
+    pub(crate) fn flush(&self) -> io::Result<()> {
+        let result = self.inner.flush();
+        match &self.split {
+            Some((_, split)) => result.and(split.flush()),
+            None => result,
+        }
     }
 }
 
@@ -71,8 +125,18 @@ impl Writer {
 #[derive(Debug)]
 pub(crate) struct Builder {
     target: Target,
+    // Whether to write a pipe target's bytes as-is, without styles
+    raw: bool,
+    // Whether the target keeps records apart itself, like a datagram socket
+    datagrams: bool,
     write_style: WriteStyle,
     is_test: bool,
+    error_policy: WriteErrorPolicy,
+    errors: WriteErrors,
+    buffer_capacity: Option<usize>,
+    flush_interval: Option<Duration>,
+    flush_level: Option<Level>,
+    framing: Option<Framing>,
     built: bool,
 }
 
@@ -81,8 +145,16 @@ impl Builder {
     pub(crate) fn new() -> Self {
         Builder {
             target: Default::default(),
+            raw: false,
+            datagrams: false,
             write_style: Default::default(),
             is_test: false,
+            error_policy: Default::default(),
+            errors: Default::default(),
+            buffer_capacity: None,
+            flush_interval: None,
+            flush_level: None,
+            framing: None,
             built: false,
         }
     }
@@ -90,6 +162,35 @@ impl Builder {
     /// Set the target to write to.
     pub(crate) fn target(&mut self, target: Target) -> &mut Self {
         self.target = target;
+        self.raw = false;
+        self.datagrams = false;
+        self
+    }
+
+    /// Whether the target is a standard stream connected to the systemd journal.
+    pub(crate) fn is_journal_stream(&self) -> bool {
+        !self.raw && journal::is_journal_stream(&self.target)
+    }
+
+    /// Set a pipe to write to, for binary output that must not be adapted for styles.
+    ///
+    /// Each write is a record of its own, so records aren't framed.
+    pub(crate) fn raw_target(&mut self, pipe: Box<dyn io::Write + Send + 'static>) -> &mut Self {
+        self.target = Target::Pipe(pipe);
+        self.raw = true;
+        self.datagrams = true;
+        self
+    }
+
+    /// Set a pipe to write to that sends each write as a record of its own, so records
+    /// aren't framed.
+    pub(crate) fn datagram_target(
+        &mut self,
+        pipe: Box<dyn io::Write + Send + 'static>,
+    ) -> &mut Self {
+        self.target = Target::Pipe(pipe);
+        self.raw = false;
+        self.datagrams = true;
         self
     }
 
@@ -115,18 +216,83 @@ impl Builder {
         self
     }
 
+    /// Set what to do when writing fails.
+    pub(crate) fn error_policy(&mut self, policy: WriteErrorPolicy) -> &mut Self {
+        self.error_policy = policy;
+        self
+    }
+
+    /// Buffer output to standard streams, writing it out in blocks of `capacity` bytes.
+    pub(crate) fn buffered(&mut self, capacity: usize) -> &mut Self {
+        self.buffer_capacity = Some(capacity);
+        self
+    }
+
+    /// Set how long a record may wait in the buffer.
+    pub(crate) fn flush_interval(&mut self, interval: Duration) -> &mut Self {
+        self.flush_interval = Some(interval);
+        self
+    }
+
+    /// Set the level of the records that write out the buffer right away.
+    pub(crate) fn flush_level(&mut self, level: Level) -> &mut Self {
+        self.flush_level = Some(level);
+        self
+    }
+
+    /// Set how records are delimited.
+    pub(crate) fn framing(&mut self, framing: Framing) -> &mut Self {
+        self.framing = Some(framing);
+        self
+    }
+
+    /// The write errors of the writer once it's built.
+    pub(crate) fn errors(&self) -> &WriteErrors {
+        &self.errors
+    }
+
     /// Build a terminal writer.
     pub(crate) fn build(&mut self) -> Writer {
         assert!(!self.built, "attempt to re-use consumed builder");
         self.built = true;
 
+        let on_error = ErrorHandler::new(mem::take(&mut self.error_policy), |target| {
+            Builder::new()
+                .target(target)
+                .write_style(self.write_style)
+                .is_test(self.is_test)
+                .build()
+        });
+        let (inner, split) = match mem::take(&mut self.target) {
+            Target::Split(level) => (
+                self.build_stream(Target::Stderr),
+                Some((level, self.build_stream(Target::Stdout))),
+            ),
+            target => (self.build_stream(target), None),
+        };
+        Writer {
+            inner,
+            split,
+            framing: self.framing.filter(|_| !self.datagrams),
+            on_error,
+            errors: self.errors.clone(),
+        }
+    }
+
+    fn build_stream(&self, target: Target) -> BufferWriter {
+        if self.raw {
+            if let Target::Pipe(pipe) = target {
+                return BufferWriter::raw_pipe(Box::new(Mutex::new(pipe)));
+            }
+        }
+
         let color_choice = self.write_style;
         #[cfg(feature = "auto-color")]
         let color_choice = if color_choice == WriteStyle::Auto {
-            match &self.target {
+            match &target {
                 Target::Stdout => anstream::AutoStream::choice(&io::stdout()).into(),
                 Target::Stderr => anstream::AutoStream::choice(&io::stderr()).into(),
-                Target::Pipe(_) => color_choice,
+                Target::Pipe(_) | Target::Split(_) | Target::Callback(_) => color_choice,
             }
         } else {
             color_choice
@@ -137,13 +303,34 @@ impl Builder {
             color_choice
         };
 
-        let writer = match mem::take(&mut self.target) {
+        if let Some(buffering) = self.buffering() {
+            match target {
+                Target::Stdout => return BufferWriter::buffered_stdout(buffering, color_choice),
+                Target::Stderr | Target::Split(_) => {
+                    return BufferWriter::buffered_stderr(buffering, color_choice)
+                }
+                _ => {}
+            }
+        }
+
+        match target {
             Target::Stdout => BufferWriter::stdout(self.is_test, color_choice),
-            Target::Stderr => BufferWriter::stderr(self.is_test, color_choice),
+            Target::Stderr | Target::Split(_) => BufferWriter::stderr(self.is_test, color_choice),
             Target::Pipe(pipe) => BufferWriter::pipe(Box::new(Mutex::new(pipe)), color_choice),
-        };
+            Target::Callback(callback) => BufferWriter::callback(callback, color_choice),
+        }
+    }
 
-        Writer { inner: writer }
+    /// How to buffer standard streams, if at all.
+    ///
+    /// Output isn't buffered for tests, since it's captured per test.
+    fn buffering(&self) -> Option<Buffering> {
+        let capacity = self.buffer_capacity.filter(|_| !self.is_test)?;
+        Some(Buffering {
+            capacity,
+            interval: self.flush_interval.unwrap_or(Duration::from_secs(1)),
+            level: self.flush_level.unwrap_or(Level::Error),
+        })
     }
 }
 
@@ -153,7 +340,7 @@ impl Default for Builder {
     }
 }
 
-fn parse_write_style(spec: &str) -> WriteStyle {
+pub(crate) fn parse_write_style(spec: &str) -> WriteStyle {
     match spec {
         "auto" => WriteStyle::Auto,
         "always" => WriteStyle::Always,
@@ -164,8 +351,106 @@ fn parse_write_style(spec: &str) -> WriteStyle {
 
 #[cfg(test)]
 mod tests {
+    use std::sync::Arc;
+
     use super::*;
 
+    /// Replaces the streams of a split writer with callbacks capturing what each one
+    /// writes, keeping their styles.
+    fn capture_split(writer: &mut Writer) -> (Arc<Mutex<String>>, Arc<Mutex<String>>) {
+        let capture = |stream: &mut BufferWriter| {
+            let captured = Arc::new(Mutex::new(String::new()));
+            *stream = BufferWriter::callback(
+                Box::new({
+                    let captured = captured.clone();
+                    move |buf: &[u8], _: &RecordMeta<'_>| {
+                        captured
+                            .lock()
+                            .unwrap()
+                            .push_str(&String::from_utf8_lossy(buf));
+                    }
+                }),
+                stream.write_style(),
+            );
+            captured
+        };
+
+        let stderr = capture(&mut writer.inner);
+        let stdout = capture(&mut writer.split.as_mut().unwrap().1);
+        (stderr, stdout)
+    }
+
+    fn print(writer: &Writer, level: Level, message: &str) {
+        let mut buf = writer.buffer();
+        buf.write(message.as_bytes()).unwrap();
+        let record = log::Record::builder().level(level).build();
+        writer.print(&buf, &RecordMeta::new(&record)).unwrap();
+    }
+
+    #[test]
+    fn split_by_level() {
+        let mut writer = Builder::new()
+            .target(Target::Split(Level::Warn))
+            .write_style(WriteStyle::Never)
+            .build();
+        assert!(format!("{:?}", writer.inner).contains("stderr"));
+        assert!(format!("{:?}", writer.split.as_ref().unwrap().1).contains("stdout"));
+
+        let (stderr, stdout) = capture_split(&mut writer);
+        for level in [
+            Level::Error,
+            Level::Warn,
+            Level::Info,
+            Level::Debug,
+            Level::Trace,
+        ] {
+            print(&writer, level, &format!("{level}\n"));
+        }
+
+        assert_eq!(*stderr.lock().unwrap(), "ERROR\nWARN\n");
+        assert_eq!(*stdout.lock().unwrap(), "INFO\nDEBUG\nTRACE\n");
+        assert_eq!(writer.write_style(), WriteStyle::Never);
+    }
+
+    #[test]
+    #[cfg(feature = "auto-color")]
+    fn split_by_level_detects_styles_per_stream() {
+        let writer = Builder::new().target(Target::Split(Level::Warn)).build();
+
+        let detected = |choice: anstream::ColorChoice| match WriteStyle::from(choice) {
+            WriteStyle::Auto => WriteStyle::Never,
+            style => style,
+        };
+        assert_eq!(
+            writer.inner.write_style(),
+            detected(anstream::AutoStream::choice(&io::stderr()))
+        );
+        assert_eq!(
+            writer.split.as_ref().unwrap().1.write_style(),
+            detected(anstream::AutoStream::choice(&io::stdout()))
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "color")]
+    fn split_by_level_strips_styles_per_stream() {
+        let mut writer = Builder::new()
+            .target(Target::Split(Level::Warn))
+            .write_style(WriteStyle::Never)
+            .build();
+        // Like when only standard output is a terminal
+        writer.split.as_mut().unwrap().1 = BufferWriter::stdout(true, WriteStyle::Always);
+        let (stderr, stdout) = capture_split(&mut writer);
+
+        // Records are formatted with styles, since one of the streams writes them
+        assert_eq!(writer.write_style(), WriteStyle::Always);
+
+        print(&writer, Level::Error, "\x1b[31mfailed\x1b[0m\n");
+        print(&writer, Level::Info, "\x1b[32mdone\x1b[0m\n");
+        assert_eq!(*stderr.lock().unwrap(), "failed\n");
+        assert_eq!(*stdout.lock().unwrap(), "\x1b[32mdone\x1b[0m\n");
+    }
+
     #[test]
     fn parse_write_style_valid() {
         let inputs = vec![
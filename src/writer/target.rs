@@ -1,4 +1,8 @@
-/// Log target, either `stdout`, `stderr` or a custom pipe.
+use std::time::SystemTime;
+
+use log::{Level, Record};
+
+/// Log target, either `stdout`, `stderr`, both split by level, a custom pipe, or a callback.
 #[non_exhaustive]
 #[derive(Default)]
 pub enum Target {
@@ -9,6 +13,37 @@ pub enum Target {
     Stderr,
     /// Logs will be sent to a custom pipe.
     Pipe(Box<dyn std::io::Write + Send + 'static>),
+    /// Logs at the given level or more severe will be sent to standard error, and the
+    /// others to standard output.
+    ///
+    /// Each stream gets its own choice of whether to write styles.
+    Split(Level),
+    /// Logs will be passed to a function, one record at a time, along with their metadata.
+    ///
+    /// The record is formatted with styles if the write style is
+    /// [`Always`][crate::WriteStyle::Always].
+    ///
+    /// # Examples
+    ///
+    /// Show records in a log panel:
+    ///
+    /// ```
+    /// use std::sync::mpsc;
+    ///
+    /// use env_logger::{Builder, Target};
+    ///
+    /// let (sender, panel) = mpsc::channel();
+    /// let sender = std::sync::Mutex::new(sender);
+    /// Builder::new()
+    ///     .target(Target::Callback(Box::new(move |bytes, meta| {
+    ///         let line = String::from_utf8_lossy(bytes).into_owned();
+    ///         let _ = sender.lock().unwrap().send((meta.level(), line));
+    ///     })))
+    ///     .init();
+    /// # drop(panel);
+    /// ```
+    #[allow(clippy::type_complexity)]
+    Callback(Box<dyn Fn(&[u8], &RecordMeta<'_>) + Send + Sync + 'static>),
 }
 
 impl std::fmt::Debug for Target {
@@ -20,7 +55,90 @@ impl std::fmt::Debug for Target {
                 Self::Stdout => "stdout",
                 Self::Stderr => "stderr",
                 Self::Pipe(_) => "pipe",
+                Self::Split(_) => "split",
+                Self::Callback(_) => "callback",
             }
         )
     }
 }
+
+pub(crate) type CallbackFn = dyn Fn(&[u8], &RecordMeta<'_>) + Send + Sync + 'static;
+
+/// The metadata of a record, as passed to a [`Target::Callback`] along with the record.
+#[derive(Clone, Copy, Debug)]
+pub struct RecordMeta<'a> {
+    level: Level,
+    target: &'a str,
+    file: Option<&'a str>,
+    line: Option<u32>,
+    timestamp: SystemTime,
+}
+
+impl<'a> RecordMeta<'a> {
+    pub(crate) fn new(record: &Record<'a>) -> Self {
+        Self {
+            level: record.level(),
+            target: record.target(),
+            file: record.file(),
+            line: record.line(),
+            timestamp: SystemTime::now(),
+        }
+    }
+
+    /// The level of the record.
+    pub fn level(&self) -> Level {
+        self.level
+    }
+
+    /// The target of the record.
+    pub fn target(&self) -> &'a str {
+        self.target
+    }
+
+    /// The source file of the record, if known.
+    pub fn file(&self) -> Option<&'a str> {
+        self.file
+    }
+
+    /// The source line of the record, if known.
+    pub fn line(&self) -> Option<u32> {
+        self.line
+    }
+
+    /// When the record was logged.
+    pub fn timestamp(&self) -> SystemTime {
+        self.timestamp
+    }
+}
+
+/// A [`RecordMeta`] that owns its strings, to write a record later.
+#[derive(Clone, Debug)]
+pub(crate) struct OwnedRecordMeta {
+    level: Level,
+    target: String,
+    file: Option<String>,
+    line: Option<u32>,
+    timestamp: SystemTime,
+}
+
+impl OwnedRecordMeta {
+    pub(crate) fn new(meta: &RecordMeta<'_>) -> Self {
+        Self {
+            level: meta.level,
+            target: meta.target.to_owned(),
+            file: meta.file.map(str::to_owned),
+            line: meta.line,
+            timestamp: meta.timestamp,
+        }
+    }
+
+    pub(crate) fn as_meta(&self) -> RecordMeta<'_> {
+        RecordMeta {
+            level: self.level,
+            target: &self.target,
+            file: self.file.as_deref(),
+            line: self.line,
+            timestamp: self.timestamp,
+        }
+    }
+}
@@ -0,0 +1,85 @@
+#![allow(clippy::unwrap_used)]
+
+#[cfg(unix)]
+fn main() {
+    use std::io::{BufRead as _, BufReader, Write as _};
+    use std::os::unix::fs::PermissionsExt as _;
+    use std::os::unix::net::UnixStream;
+    use std::time::{Duration, Instant};
+
+    use log::LevelFilter;
+
+    let path = std::env::temp_dir().join(format!("env_logger-admin-{}.sock", std::process::id()));
+
+    let mut builder = env_logger::Builder::new();
+    builder.parse_filters("warn,db=info");
+    let handle = builder.filter_handle();
+    builder.init();
+    assert_eq!(LevelFilter::Info, log::max_level());
+
+    let admin = handle.serve_admin(&path).unwrap();
+    let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o600);
+
+    // A client that never finishes its request doesn't hold up the others
+    let mut idle = UnixStream::connect(&path).unwrap();
+    write!(idle, "get").unwrap();
+
+    let stream = UnixStream::connect(&path).unwrap();
+    let mut reader = BufReader::new(&stream);
+    let mut request = |request: &str| {
+        writeln!(&stream, "{request}").unwrap();
+        let mut response = String::new();
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            response.push_str(&line);
+            if line == "ok\n" || line.starts_with("error: ") {
+                return response;
+            }
+        }
+    };
+
+    let start = Instant::now();
+    assert_eq!(request("get"), "warn,db=info\nok\n");
+    assert!(start.elapsed() < Duration::from_secs(1));
+    assert_eq!(request("targets"), "warn\ndb=info\nok\n");
+
+    assert_eq!(request("set info, db=trace"), "ok\n");
+    assert_eq!(request("get"), "info,db=trace\nok\n");
+    assert_eq!(LevelFilter::Trace, log::max_level());
+    assert!(log::log_enabled!(target: "db::pool", log::Level::Trace));
+
+    assert_eq!(
+        request("set db=loud"),
+        "error: error parsing logger filter: invalid logging spec 'loud'\n"
+    );
+    assert_eq!(request("get"), "info,db=trace\nok\n");
+
+    assert_eq!(request("set"), "error: `set` needs a filter\n");
+    assert_eq!(request("get info"), "error: `get` takes no arguments\n");
+    assert_eq!(request("level"), "error: unknown command `level`\n");
+
+    assert_eq!(request("reset"), "ok\n");
+    assert_eq!(request("get"), "warn,db=info\nok\n");
+    assert_eq!(LevelFilter::Info, log::max_level());
+
+    // A socket that is still served isn't replaced
+    assert!(handle.serve_admin(&path).is_err());
+
+    // Dropping the socket doesn't wait for connected clients
+    let start = Instant::now();
+    drop(admin);
+    assert!(start.elapsed() < Duration::from_secs(1));
+    assert!(!path.exists());
+    drop(stream);
+    drop(idle);
+
+    // A stale socket is replaced
+    drop(std::os::unix::net::UnixListener::bind(&path).unwrap());
+    let admin = handle.serve_admin(&path).unwrap();
+    assert!(UnixStream::connect(admin.path()).is_ok());
+}
+
+#[cfg(not(unix))]
+fn main() {}
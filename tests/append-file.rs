@@ -0,0 +1,62 @@
+#![allow(clippy::unwrap_used)]
+
+use std::env;
+use std::process::Command;
+
+use env_logger::file::AppendFile;
+use env_logger::Target;
+
+const WORKER_ENV: &str = "APPEND_FILE_WORKER";
+const WORKERS: usize = 4;
+const RECORDS: usize = 200;
+const RECORD_LEN: usize = 16 * 1024;
+
+fn main() {
+    if let Ok(worker) = env::var(WORKER_ENV) {
+        return log_records(worker.parse().unwrap());
+    }
+
+    let path = env::temp_dir().join(format!("env_logger-append-file-{}.log", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    let workers: Vec<_> = (0..WORKERS)
+        .map(|worker| {
+            Command::new(env::current_exe().unwrap())
+                .env(WORKER_ENV, worker.to_string())
+                .env("APPEND_FILE_PATH", &path)
+                .spawn()
+                .unwrap()
+        })
+        .collect();
+    for mut worker in workers {
+        assert!(worker.wait().unwrap().success());
+    }
+
+    let contents = std::fs::read_to_string(&path).unwrap();
+    let _ = std::fs::remove_file(&path);
+    let lines: Vec<_> = contents.lines().collect();
+    assert_eq!(lines.len(), WORKERS * RECORDS);
+    for line in lines {
+        // Each record is a worker's digit repeated, so interleaved records mix digits
+        let first = line.as_bytes()[0];
+        assert_eq!(line.len(), RECORD_LEN, "{line}");
+        assert!(line.bytes().all(|b| b == first), "{line}");
+    }
+}
+
+fn log_records(worker: usize) {
+    let file = AppendFile::open(env::var_os("APPEND_FILE_PATH").unwrap()).unwrap();
+    env_logger::Builder::new()
+        .filter_level(log::LevelFilter::Info)
+        .format(|buf, record| {
+            use std::io::Write as _;
+            writeln!(buf, "{}", record.args())
+        })
+        .target(Target::Pipe(Box::new(file)))
+        .init();
+
+    let record = worker.to_string().repeat(RECORD_LEN);
+    for _ in 0..RECORDS {
+        log::info!("{record}");
+    }
+}
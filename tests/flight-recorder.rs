@@ -0,0 +1,48 @@
+#![allow(clippy::unwrap_used)]
+
+use std::io;
+use std::sync::{Arc, Mutex};
+
+use env_logger::Target;
+use log::LevelFilter;
+
+#[derive(Clone, Default)]
+struct Pipe(Arc<Mutex<Vec<u8>>>);
+
+impl io::Write for Pipe {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn main() {
+    let pipe = Pipe::default();
+    env_logger::Builder::new()
+        .filter_level(LevelFilter::Info)
+        .flight_recorder(10, LevelFilter::Debug)
+        .format(|buf, record| {
+            use std::io::Write as _;
+            writeln!(buf, "{}", record.args())
+        })
+        .target(Target::Pipe(Box::new(pipe.clone())))
+        .init();
+    assert_eq!(log::max_level(), LevelFilter::Debug);
+
+    log::info!("starting");
+    log::debug!("context");
+    log::trace!("too detailed");
+
+    let panicked = std::thread::spawn(|| {
+        log::debug!("about to fail");
+        panic!("failed");
+    })
+    .join();
+    assert!(panicked.is_err());
+
+    let output = String::from_utf8(pipe.0.lock().unwrap().clone()).unwrap();
+    assert_eq!(output, "starting\ncontext\nabout to fail\n");
+}
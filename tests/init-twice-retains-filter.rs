@@ -1,8 +1,30 @@
 #![allow(clippy::unwrap_used)]
 
 use std::env;
+use std::io;
 use std::process;
 use std::str;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// A target that records when it's dropped.
+struct Target(Arc<AtomicBool>);
+
+impl io::Write for Target {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Drop for Target {
+    fn drop(&mut self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+}
 
 fn main() {
     if env::var("YOU_ARE_TESTING_NOW").is_ok() {
@@ -13,12 +35,16 @@ fn main() {
 
         // Init again using a different max level
         // This shouldn't clobber the level that was previously set
+        let dropped = Arc::new(AtomicBool::new(false));
         env_logger::Builder::new()
             .parse_filters("info")
+            .target(env_logger::Target::Pipe(Box::new(Target(dropped.clone()))))
             .try_init()
             .unwrap_err();
 
         assert_eq!(log::LevelFilter::Debug, log::max_level());
+        // The logger that wasn't set isn't leaked
+        assert!(dropped.load(Ordering::SeqCst));
         return;
     }
 
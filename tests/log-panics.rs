@@ -0,0 +1,58 @@
+#![allow(clippy::unwrap_used)]
+
+use std::io;
+use std::sync::{Arc, Mutex};
+
+use env_logger::Target;
+
+#[derive(Clone, Default)]
+struct Pipe(Arc<Mutex<Vec<u8>>>);
+
+impl io::Write for Pipe {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn main() {
+    let pipe = Pipe::default();
+    env_logger::Builder::new()
+        // Panics are written even though the `panic` target isn't enabled
+        .parse_filters("myapp=debug")
+        .log_panics(true)
+        .log_panic_backtraces(true)
+        .format(|buf, record| {
+            use std::io::Write as _;
+            writeln!(
+                buf,
+                "{} {} {}:{}: {}",
+                record.level(),
+                record.target(),
+                record.file().unwrap(),
+                record.line().unwrap(),
+                record.args()
+            )
+        })
+        .target(Target::Pipe(Box::new(pipe.clone())))
+        .init();
+
+    let line = line!() + 4;
+    let panicked = std::thread::Builder::new()
+        .name("worker".to_owned())
+        .spawn(|| {
+            panic!("failed with {}", 42);
+        })
+        .unwrap()
+        .join();
+    assert!(panicked.is_err());
+
+    let output = String::from_utf8(pipe.0.lock().unwrap().clone()).unwrap();
+    let expected = format!(
+        "ERROR panic tests/log-panics.rs:{line}: thread 'worker' panicked at tests/log-panics.rs:{line}:13:\nfailed with 42\nstack backtrace:\n"
+    );
+    assert!(output.starts_with(&expected), "{output}");
+}
@@ -0,0 +1,92 @@
+#![allow(clippy::unwrap_used)]
+
+use std::io::{BufRead as _, BufReader};
+use std::net::TcpListener;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use env_logger::net::NetworkTarget;
+use env_logger::Target;
+
+fn main() {
+    // Find a free port, then leave it closed so the first connection attempt fails
+    let addr = TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap();
+
+    let mut target = NetworkTarget::tcp(addr).unwrap();
+    target
+        .buffer_capacity(3)
+        .backoff(Duration::from_millis(10), Duration::from_millis(10));
+
+    env_logger::Builder::new()
+        .filter_level(log::LevelFilter::Info)
+        .format(|buf, record| {
+            use std::io::Write as _;
+            writeln!(buf, "{}", record.args())
+        })
+        .target(Target::Pipe(Box::new(target)))
+        .init();
+
+    // Queued while disconnected, and the first one is dropped once the queue is full
+    log::info!("dropped");
+    log::info!("first");
+    log::info!("second");
+    log::info!("third");
+
+    let listener = TcpListener::bind(addr).unwrap();
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let server = {
+        let received = received.clone();
+        thread::spawn(move || {
+            // Accept twice, closing the first connection after one line to force a reconnect
+            for lines in [Some(4), None] {
+                let (stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream);
+                let mut count = 0;
+                loop {
+                    let mut line = String::new();
+                    if reader.read_line(&mut line).unwrap() == 0 {
+                        break;
+                    }
+                    let done = line == "done\n";
+                    received.lock().unwrap().push(line);
+                    count += 1;
+                    if done || Some(count) == lines {
+                        break;
+                    }
+                }
+            }
+        })
+    };
+
+    // Wait for the queue to be sent once the listener is up
+    let start = Instant::now();
+    while received.lock().unwrap().len() < 3 {
+        assert!(start.elapsed() < Duration::from_secs(10), "not reconnected");
+        thread::sleep(Duration::from_millis(5));
+    }
+    log::info!("fourth");
+
+    // Keep writing until the broken connection is noticed and replaced
+    for _ in 0..500 {
+        thread::sleep(Duration::from_millis(20));
+        log::info!("retry");
+        if server.is_finished() {
+            break;
+        }
+        if received.lock().unwrap().len() > 4 {
+            log::info!("done");
+        }
+    }
+    server.join().unwrap();
+
+    let received = received.lock().unwrap();
+    assert_eq!(
+        received[..4],
+        ["first\n", "second\n", "third\n", "fourth\n"]
+    );
+    assert_eq!(received.last().unwrap(), "done\n");
+}
@@ -0,0 +1,58 @@
+#![allow(clippy::unwrap_used)]
+
+#[cfg(unix)]
+fn main() {
+    use std::io;
+    use std::sync::{Arc, Mutex};
+    use std::time::{Duration, Instant};
+
+    use env_logger::Target;
+    use log::LevelFilter;
+
+    #[derive(Clone, Default)]
+    struct Pipe(Arc<Mutex<Vec<u8>>>);
+
+    impl io::Write for Pipe {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let pipe = Pipe::default();
+    let output = || String::from_utf8(pipe.0.lock().unwrap().clone()).unwrap();
+
+    let mut builder = env_logger::Builder::new();
+    builder
+        .parse_filters("warn")
+        .format(|buf, record| {
+            use std::io::Write as _;
+            writeln!(buf, "{}", record.args())
+        })
+        .target(Target::Pipe(Box::new(pipe.clone())));
+    let handle = builder.filter_handle();
+    builder.init();
+
+    let _watcher = handle.watch_signals().unwrap();
+    signal_hook::low_level::raise(signal_hook::consts::SIGUSR2).unwrap();
+
+    let start = Instant::now();
+    while output().is_empty() {
+        assert!(start.elapsed() < Duration::from_secs(10), "no level change");
+        std::thread::sleep(Duration::from_millis(5));
+    }
+    assert_eq!(handle.get().to_string(), "error");
+    assert_eq!(log::max_level(), LevelFilter::Error);
+
+    // The notice is written although the new level leaves out warnings
+    assert_eq!(output(), "log level lowered to ERROR by SIGUSR2\n");
+
+    log::warn!("dropped");
+    assert_eq!(output(), "log level lowered to ERROR by SIGUSR2\n");
+}
+
+#[cfg(not(unix))]
+fn main() {}